@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS refresh_token_reuse_events;
+DROP INDEX IF EXISTS refresh_tokens_family_id_idx;
+ALTER TABLE refresh_tokens DROP COLUMN family_id, DROP COLUMN created_at, DROP COLUMN consumed_at;
+DELETE FROM schema_migrations WHERE version = 5;
@@ -0,0 +1,10 @@
+ALTER TABLE refresh_tokens
+    ADD COLUMN family_id UUID NOT NULL DEFAULT gen_random_uuid(),
+    ADD COLUMN created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    ADD COLUMN consumed_at TIMESTAMP WITH TIME ZONE;
+
+ALTER TABLE refresh_tokens ALTER COLUMN family_id DROP DEFAULT;
+
+CREATE INDEX IF NOT EXISTS refresh_tokens_family_id_idx ON refresh_tokens (family_id);
+
+CREATE TABLE IF NOT EXISTS refresh_token_reuse_events (id BIGSERIAL PRIMARY KEY, user_id INTEGER NOT NULL, family_id UUID NOT NULL, detected_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW());
@@ -0,0 +1,4 @@
+-- Existing rows hold plaintext tokens, they are invalidated and their owners must log in again
+DELETE FROM refresh_tokens;
+
+ALTER TABLE refresh_tokens RENAME COLUMN token TO token_hash;
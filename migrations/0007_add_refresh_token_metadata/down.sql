@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS refresh_tokens_user_id_idx;
+ALTER TABLE refresh_tokens DROP COLUMN session_created_at, DROP COLUMN user_agent, DROP COLUMN ip_address;
+DELETE FROM schema_migrations WHERE version = 7;
@@ -0,0 +1,6 @@
+ALTER TABLE refresh_tokens
+    ADD COLUMN session_created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    ADD COLUMN user_agent TEXT,
+    ADD COLUMN ip_address TEXT;
+
+CREATE INDEX IF NOT EXISTS refresh_tokens_user_id_idx ON refresh_tokens (user_id);
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS access_token_cutoffs;
+DROP TABLE IF EXISTS revoked_access_tokens;
+DELETE FROM schema_migrations WHERE version = 8;
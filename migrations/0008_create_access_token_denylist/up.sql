@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS revoked_access_tokens (
+    jti UUID PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    revoked_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS revoked_access_tokens_expires_at_idx ON revoked_access_tokens (expires_at);
+
+-- Access tokens of the user issued before not_before are rejected
+CREATE TABLE IF NOT EXISTS access_token_cutoffs (
+    user_id INTEGER PRIMARY KEY,
+    not_before TIMESTAMP WITH TIME ZONE NOT NULL
+);
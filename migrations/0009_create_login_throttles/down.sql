@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS login_throttles;
+DELETE FROM schema_migrations WHERE version = 9;
@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS user_tokens;
+DROP INDEX IF EXISTS users_email_key;
+ALTER TABLE users DROP COLUMN email, DROP COLUMN email_verified_at;
+DELETE FROM schema_migrations WHERE version = 11;
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS personal_access_tokens;
+DELETE FROM schema_migrations WHERE version = 13;
@@ -0,0 +1,5 @@
+-- Space-separated scope a session was limited to at login, NULL if it was
+-- not. Rotated refresh tokens keep the scope of their family.
+ALTER TABLE refresh_tokens ADD COLUMN IF NOT EXISTS scope TEXT;
+
+ALTER TABLE mfa_challenges ADD COLUMN IF NOT EXISTS scope TEXT;
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS auth_events;
+DELETE FROM schema_migrations WHERE version = 15;
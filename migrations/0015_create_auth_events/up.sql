@@ -0,0 +1,16 @@
+-- Audit trail of logins, refreshes and logouts. Rows outlive the user they
+-- belong to, username keeps the name a failed login was attempted for.
+CREATE TABLE IF NOT EXISTS auth_events (
+    id BIGSERIAL PRIMARY KEY,
+    user_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    username VARCHAR(255),
+    event_type VARCHAR(32) NOT NULL,
+    outcome VARCHAR(16) NOT NULL,
+    reason VARCHAR(64),
+    ip_address TEXT,
+    user_agent TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS auth_events_user_id_idx ON auth_events (user_id, id DESC);
+CREATE INDEX IF NOT EXISTS auth_events_created_at_idx ON auth_events (created_at);
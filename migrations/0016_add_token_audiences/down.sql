@@ -0,0 +1,3 @@
+ALTER TABLE mfa_challenges DROP COLUMN IF EXISTS audience;
+ALTER TABLE refresh_tokens DROP COLUMN IF EXISTS audience;
+DELETE FROM schema_migrations WHERE version = 16;
@@ -0,0 +1,5 @@
+-- Audience a session was issued for at login, NULL for the default one.
+-- Rotated refresh tokens keep the audience of their family.
+ALTER TABLE refresh_tokens ADD COLUMN IF NOT EXISTS audience TEXT;
+
+ALTER TABLE mfa_challenges ADD COLUMN IF NOT EXISTS audience TEXT;
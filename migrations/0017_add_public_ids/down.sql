@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS posts_public_id_idx;
+ALTER TABLE posts DROP COLUMN IF EXISTS public_id;
+DROP INDEX IF EXISTS users_public_id_idx;
+ALTER TABLE users DROP COLUMN IF EXISTS public_id;
+DELETE FROM schema_migrations WHERE version = 17;
@@ -0,0 +1,8 @@
+-- Stable, non-sequential ids exposed in URLs and in the `sub` claim. The
+-- integer ids stay the primary keys and keep working in routes until the
+-- deprecation window ends.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS public_id UUID NOT NULL DEFAULT gen_random_uuid();
+CREATE UNIQUE INDEX IF NOT EXISTS users_public_id_idx ON users (public_id);
+
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS public_id UUID NOT NULL DEFAULT gen_random_uuid();
+CREATE UNIQUE INDEX IF NOT EXISTS posts_public_id_idx ON posts (public_id);
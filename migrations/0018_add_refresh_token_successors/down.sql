@@ -0,0 +1,2 @@
+ALTER TABLE refresh_tokens DROP COLUMN IF EXISTS successor_jti;
+DELETE FROM schema_migrations WHERE version = 18;
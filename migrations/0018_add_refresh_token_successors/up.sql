@@ -0,0 +1,4 @@
+-- `jti` of the access token issued when the token was rotated. Together
+-- with `consumed_at` it lets a concurrent refresh within the grace period
+-- get the same successor pair.
+ALTER TABLE refresh_tokens ADD COLUMN IF NOT EXISTS successor_jti UUID;
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS cleanup_runs;
+DELETE FROM schema_migrations WHERE version = 19;
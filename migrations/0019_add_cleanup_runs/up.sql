@@ -0,0 +1,6 @@
+-- When each cleanup job last ran. Only bookkeeping: which instance runs a
+-- job is decided by an advisory lock.
+CREATE TABLE IF NOT EXISTS cleanup_runs (
+    job TEXT PRIMARY KEY,
+    last_run_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,970 @@
+use std::{
+    collections::HashMap, env, fs, net::IpAddr, path::Path, str::FromStr,
+};
+
+use argon2::Params;
+use jsonwebtoken::{Algorithm, Validation};
+use serde::Deserialize;
+use strum::EnumString;
+use time::Duration;
+
+use crate::errors::config_errors::ConfigError;
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+const MIN_SECRET_LEN: usize = 32;
+
+/// Application settings. Defaults are overridden by the optional TOML file
+/// (`APP_CONFIG_FILE`, `config.toml` by default), which is in turn
+/// overridden by environment variables.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub jwt: JwtConfig,
+    pub password: PasswordConfig,
+    pub password_policy: PasswordPolicyConfig,
+    pub login_throttle: LoginThrottleConfig,
+    pub mfa: MfaConfig,
+    pub mail: MailConfig,
+    pub account: AccountConfig,
+    pub cookies: CookieConfig,
+    pub admin: AdminConfig,
+    pub scheduler: SchedulerConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    /// Reverse proxies whose `X-Forwarded-For` is trusted. Requests from
+    /// them are attributed to the last address in the header that is not
+    /// a trusted proxy, every other request to its peer address.
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+}
+
+/// Access tokens are signed with HS256 and `secret` unless asymmetric
+/// `keys` are configured, in which case the key named by `signing_kid`
+/// signs and every listed key verifies.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JwtConfig {
+    pub secret: String,
+    pub access_token_ttl_secs: i64,
+    pub refresh_token_ttl_secs: i64,
+    /// Key for the HMAC under which refresh tokens are stored.
+    pub refresh_token_hmac_key: String,
+    pub signing_kid: String,
+    pub keys: Vec<JwtKeyConfig>,
+    /// How often the access token denylist cache is reloaded.
+    pub denylist_refresh_secs: u64,
+    /// `iss` of issued tokens.
+    pub issuer: String,
+    /// Further issuers whose tokens are accepted besides `issuer`, e.g.
+    /// while the issuer is being renamed.
+    pub accepted_issuers: Vec<String>,
+    /// Audiences tokens can be issued for and are accepted with. Logins
+    /// that ask for no audience get the first one.
+    pub audiences: Vec<String>,
+    /// Clock skew tolerated when checking `exp` and `nbf`.
+    pub leeway_secs: u64,
+    /// How long a rotated refresh token still returns the pair it was
+    /// rotated to, for clients that refresh concurrently. 0 disables it.
+    pub refresh_grace_secs: i64,
+}
+
+/// A PEM encoded `RS256` or `EdDSA` key. Retired keys keep only the public
+/// half so tokens they signed can be verified until they expire.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JwtKeyConfig {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    pub public_key_path: String,
+    pub private_key_path: Option<String>,
+}
+
+/// Argon2id cost parameters, memory cost is in KiB.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PasswordConfig {
+    pub memory_cost: u32,
+    pub time_cost: u32,
+    pub parallelism: u32,
+}
+
+/// Rules new passwords must pass on signup, change and reset.
+/// `breached_passwords_path` is a file of SHA-1 hashes, or a directory of
+/// k-anonymity range files named by the first five hex digits of the hash
+/// (as served by the Pwned Passwords range API). An empty path disables
+/// the check.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PasswordPolicyConfig {
+    pub min_entropy_bits: f64,
+    pub reject_username: bool,
+    pub breached_passwords_path: Option<String>,
+}
+
+/// Failed login throttling. Each failure locks the username for
+/// `backoff_base_secs * 2^(failures - 1)` seconds, and `max_failures`
+/// failures lock it for `lockout_secs`. Client IPs are only locked out
+/// after `ip_max_failures`, since many users may share one address.
+/// Counters start over after `reset_after_secs` without failures.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoginThrottleConfig {
+    pub max_failures: i32,
+    pub ip_max_failures: i32,
+    pub backoff_base_secs: i64,
+    pub lockout_secs: i64,
+    pub reset_after_secs: i64,
+}
+
+/// TOTP two-factor authentication. `issuer` is the account label shown by
+/// authenticator apps.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MfaConfig {
+    pub issuer: String,
+    pub challenge_ttl_secs: i64,
+    pub max_attempts: i32,
+    pub recovery_codes: usize,
+}
+
+/// How outgoing mail is delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, EnumString)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum MailTransport {
+    /// Writes mails to the application log, with their token masked.
+    Log,
+    /// Writes every mail to an `.eml` file in `file_dir`.
+    File,
+    Smtp,
+}
+
+/// Outgoing mail. `log` and `file` deliver nothing and are meant for local
+/// development and tests.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MailConfig {
+    pub transport: MailTransport,
+    pub from: String,
+    pub file_dir: String,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+}
+
+/// Password reset and email verification. `public_url` is the base of the
+/// links sent by mail.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AccountConfig {
+    pub public_url: String,
+    pub password_reset_ttl_secs: i64,
+    pub email_verification_ttl_secs: i64,
+}
+
+/// `SameSite` attribute of the auth cookies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, EnumString)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum CookieSameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+/// Cookie mode for browser clients. When enabled, `/login` and `/refresh`
+/// set the refresh token as an `HttpOnly` cookie scoped to `/refresh`, and
+/// with `access_token` the access token as well. Requests authenticated by
+/// cookie must echo the `csrf_token` cookie in the `X-CSRF-Token` header.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CookieConfig {
+    pub enabled: bool,
+    pub access_token: bool,
+    pub secure: bool,
+    pub same_site: CookieSameSite,
+    pub domain: Option<String>,
+}
+
+/// Users given the `admin` role at startup, so a fresh database has an
+/// admin who can grant roles to others.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminConfig {
+    pub user_ids: Vec<i32>,
+}
+
+/// Background jobs that purge expired rows. Every instance may run them;
+/// a Postgres advisory lock lets only one at a time run each job.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchedulerConfig {
+    pub enabled: bool,
+    /// How often each job runs, unless `intervals` sets its own.
+    pub interval_secs: u64,
+    pub intervals: HashMap<CleanupJob, u64>,
+}
+
+/// A job of the scheduler, named after the table it purges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanupJob {
+    RefreshTokens,
+    RevokedAccessTokens,
+    AccessTokenCutoffs,
+    LoginThrottles,
+    MfaChallenges,
+    UserTokens,
+    PersonalAccessTokens,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            host: "127.0.0.1".to_string(),
+            port: 3030,
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig { url: String::new(), max_connections: 5 }
+    }
+}
+
+impl Default for JwtConfig {
+    fn default() -> Self {
+        JwtConfig {
+            secret: String::new(),
+            access_token_ttl_secs: 3 * 60,
+            refresh_token_ttl_secs: 30 * 24 * 60 * 60,
+            refresh_token_hmac_key: String::new(),
+            signing_kid: "default".to_string(),
+            keys: Vec::new(),
+            denylist_refresh_secs: 30,
+            issuer: "actix_jwt_auth".to_string(),
+            accepted_issuers: Vec::new(),
+            audiences: vec!["actix_jwt_auth".to_string()],
+            leeway_secs: 30,
+            refresh_grace_secs: 10,
+        }
+    }
+}
+
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        PasswordConfig {
+            memory_cost: Params::DEFAULT_M_COST,
+            time_cost: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl Default for PasswordPolicyConfig {
+    fn default() -> Self {
+        PasswordPolicyConfig {
+            min_entropy_bits: 40.0,
+            reject_username: true,
+            breached_passwords_path: Some(
+                "data/breached_passwords.txt".to_string(),
+            ),
+        }
+    }
+}
+
+impl Default for LoginThrottleConfig {
+    fn default() -> Self {
+        LoginThrottleConfig {
+            max_failures: 5,
+            ip_max_failures: 50,
+            backoff_base_secs: 1,
+            lockout_secs: 15 * 60,
+            reset_after_secs: 15 * 60,
+        }
+    }
+}
+
+impl Default for MfaConfig {
+    fn default() -> Self {
+        MfaConfig {
+            issuer: "actix_jwt_auth".to_string(),
+            challenge_ttl_secs: 5 * 60,
+            max_attempts: 5,
+            recovery_codes: 10,
+        }
+    }
+}
+
+impl Default for CookieConfig {
+    fn default() -> Self {
+        CookieConfig {
+            enabled: false,
+            access_token: false,
+            secure: true,
+            same_site: CookieSameSite::Strict,
+            domain: None,
+        }
+    }
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        SchedulerConfig {
+            enabled: true,
+            interval_secs: 60 * 60,
+            intervals: HashMap::new(),
+        }
+    }
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        MailConfig {
+            transport: MailTransport::Log,
+            from: "actix_jwt_auth <no-reply@localhost>".to_string(),
+            file_dir: "mail".to_string(),
+            smtp_host: "localhost".to_string(),
+            smtp_port: 587,
+            smtp_username: None,
+            smtp_password: None,
+        }
+    }
+}
+
+impl Default for AccountConfig {
+    fn default() -> Self {
+        AccountConfig {
+            public_url: "http://127.0.0.1:3030".to_string(),
+            password_reset_ttl_secs: 60 * 60,
+            email_verification_ttl_secs: 24 * 60 * 60,
+        }
+    }
+}
+
+impl JwtConfig {
+    pub fn access_token_ttl(&self) -> Duration {
+        Duration::seconds(self.access_token_ttl_secs)
+    }
+
+    pub fn refresh_token_ttl(&self) -> Duration {
+        Duration::seconds(self.refresh_token_ttl_secs)
+    }
+
+    pub fn refresh_grace(&self) -> Duration {
+        Duration::seconds(self.refresh_grace_secs)
+    }
+
+    /// How long past `exp` a token is still accepted.
+    pub fn leeway(&self) -> Duration {
+        Duration::seconds(i64::try_from(self.leeway_secs).unwrap_or(i64::MAX))
+    }
+
+    /// Audience of tokens from logins that ask for none.
+    pub fn default_audience(&self) -> &str {
+        &self.audiences[0]
+    }
+
+    /// Checks applied to access tokens on top of the signature. The
+    /// algorithm is set by the key that verifies it.
+    pub fn validation(&self) -> Validation {
+        let issuers: Vec<&str> = std::iter::once(self.issuer.as_str())
+            .chain(self.accepted_issuers.iter().map(String::as_str))
+            .collect();
+
+        let mut validation = Validation::default();
+        validation.leeway = self.leeway_secs;
+        validation.validate_nbf = true;
+        validation.set_issuer(&issuers);
+        validation.set_audience(&self.audiences);
+        validation.set_required_spec_claims(&["exp", "nbf", "iss", "aud"]);
+        validation
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.access_token_ttl_secs <= 0 {
+            return Err(invalid(
+                "jwt.access_token_ttl_secs",
+                "must be greater than 0",
+            ));
+        }
+        if self.refresh_token_ttl_secs <= self.access_token_ttl_secs {
+            return Err(invalid(
+                "jwt.refresh_token_ttl_secs",
+                "must be greater than the access token TTL",
+            ));
+        }
+        if self.refresh_token_hmac_key.len() < MIN_SECRET_LEN {
+            return Err(invalid(
+                "jwt.refresh_token_hmac_key",
+                format!("must be at least {MIN_SECRET_LEN} bytes long"),
+            ));
+        }
+        if self.denylist_refresh_secs == 0 {
+            return Err(invalid(
+                "jwt.denylist_refresh_secs",
+                "must be greater than 0",
+            ));
+        }
+        if self.signing_kid.is_empty() {
+            return Err(invalid("jwt.signing_kid", "must not be empty"));
+        }
+        if self.issuer.is_empty() {
+            return Err(invalid("jwt.issuer", "must not be empty"));
+        }
+        if self.audiences.is_empty()
+            || self.audiences.iter().any(String::is_empty)
+        {
+            return Err(invalid(
+                "jwt.audiences",
+                "must list at least one non-empty audience",
+            ));
+        }
+        if i64::try_from(self.leeway_secs)
+            .map_or(true, |leeway| leeway >= self.access_token_ttl_secs)
+        {
+            return Err(invalid(
+                "jwt.leeway_secs",
+                "must be less than the access token TTL",
+            ));
+        }
+        // Повторно выданный access token не должен быть уже просрочен
+        if !(0..self.access_token_ttl_secs).contains(&self.refresh_grace_secs) {
+            return Err(invalid(
+                "jwt.refresh_grace_secs",
+                "must be at least 0 and less than the access token TTL",
+            ));
+        }
+
+        if self.keys.is_empty() {
+            if self.secret.len() < MIN_SECRET_LEN {
+                return Err(invalid(
+                    "jwt.secret",
+                    format!("must be at least {MIN_SECRET_LEN} bytes long"),
+                ));
+            }
+            return Ok(());
+        }
+
+        for (index, key) in self.keys.iter().enumerate() {
+            if !matches!(key.algorithm, Algorithm::RS256 | Algorithm::EdDSA) {
+                return Err(invalid(
+                    "jwt.keys.algorithm",
+                    format!("key '{}' must use RS256 or EdDSA", key.kid),
+                ));
+            }
+            if self.keys[..index].iter().any(|other| other.kid == key.kid) {
+                return Err(invalid(
+                    "jwt.keys.kid",
+                    format!("key id '{}' is used more than once", key.kid),
+                ));
+            }
+        }
+
+        match self.keys.iter().find(|key| key.kid == self.signing_kid) {
+            Some(key) if key.private_key_path.is_some() => Ok(()),
+            Some(_) => Err(invalid(
+                "jwt.signing_kid",
+                format!("key '{}' has no private key", self.signing_kid),
+            )),
+            None => Err(invalid(
+                "jwt.signing_kid",
+                format!("no key with id '{}' is configured", self.signing_kid),
+            )),
+        }
+    }
+}
+
+impl LoginThrottleConfig {
+    /// How long a username is locked after its `failures`-th failure.
+    pub fn username_lock(&self, failures: i32) -> Duration {
+        if failures >= self.max_failures {
+            return Duration::seconds(self.lockout_secs);
+        }
+
+        let exponent = u32::try_from(failures - 1).unwrap_or(0).min(30);
+        Duration::seconds(
+            self.backoff_base_secs
+                .saturating_mul(1 << exponent)
+                .min(self.lockout_secs),
+        )
+    }
+
+    /// How long a client IP is locked after its `failures`-th failure.
+    pub fn ip_lock(&self, failures: i32) -> Option<Duration> {
+        (failures >= self.ip_max_failures)
+            .then(|| Duration::seconds(self.lockout_secs))
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.max_failures <= 0 || self.ip_max_failures <= 0 {
+            return Err(invalid(
+                "login_throttle.max_failures",
+                "must be greater than 0",
+            ));
+        }
+        if self.backoff_base_secs < 0 || self.lockout_secs <= 0 {
+            return Err(invalid(
+                "login_throttle.lockout_secs",
+                "durations must not be negative",
+            ));
+        }
+        if self.reset_after_secs <= 0 {
+            return Err(invalid(
+                "login_throttle.reset_after_secs",
+                "must be greater than 0",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl PasswordPolicyConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if !self.min_entropy_bits.is_finite() || self.min_entropy_bits < 0.0 {
+            return Err(invalid(
+                "password_policy.min_entropy_bits",
+                "must not be negative",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl MfaConfig {
+    pub fn challenge_ttl(&self) -> Duration {
+        Duration::seconds(self.challenge_ttl_secs)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.issuer.is_empty() || self.issuer.contains(':') {
+            return Err(invalid(
+                "mfa.issuer",
+                "must be set and contain no ':'",
+            ));
+        }
+        if self.challenge_ttl_secs <= 0 || self.max_attempts <= 0 {
+            return Err(invalid(
+                "mfa.challenge_ttl_secs",
+                "TTL and attempts must be greater than 0",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl AccountConfig {
+    pub fn password_reset_ttl(&self) -> Duration {
+        Duration::seconds(self.password_reset_ttl_secs)
+    }
+
+    pub fn email_verification_ttl(&self) -> Duration {
+        Duration::seconds(self.email_verification_ttl_secs)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.password_reset_ttl_secs <= 0
+            || self.email_verification_ttl_secs <= 0
+        {
+            return Err(invalid(
+                "account.password_reset_ttl_secs",
+                "token TTLs must be greater than 0",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl SchedulerConfig {
+    pub fn interval(&self, job: CleanupJob) -> std::time::Duration {
+        std::time::Duration::from_secs(
+            self.intervals.get(&job).copied().unwrap_or(self.interval_secs),
+        )
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.interval_secs == 0 || self.intervals.values().any(|&s| s == 0) {
+            return Err(invalid(
+                "scheduler.interval_secs",
+                "intervals must be greater than 0",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl CleanupJob {
+    pub const ALL: [CleanupJob; 7] = [
+        CleanupJob::RefreshTokens,
+        CleanupJob::RevokedAccessTokens,
+        CleanupJob::AccessTokenCutoffs,
+        CleanupJob::LoginThrottles,
+        CleanupJob::MfaChallenges,
+        CleanupJob::UserTokens,
+        CleanupJob::PersonalAccessTokens,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CleanupJob::RefreshTokens => "refresh_tokens",
+            CleanupJob::RevokedAccessTokens => "revoked_access_tokens",
+            CleanupJob::AccessTokenCutoffs => "access_token_cutoffs",
+            CleanupJob::LoginThrottles => "login_throttles",
+            CleanupJob::MfaChallenges => "mfa_challenges",
+            CleanupJob::UserTokens => "user_tokens",
+            CleanupJob::PersonalAccessTokens => "personal_access_tokens",
+        }
+    }
+}
+
+impl CookieConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.same_site == CookieSameSite::None && !self.secure {
+            return Err(invalid(
+                "cookies.same_site",
+                "\"none\" requires cookies.secure",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl PasswordConfig {
+    pub fn params(&self) -> Result<Params, argon2::Error> {
+        Params::new(self.memory_cost, self.time_cost, self.parallelism, None)
+    }
+}
+
+impl AppConfig {
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut config = match env::var("APP_CONFIG_FILE") {
+            Ok(path) => Self::from_file(&path)?,
+            Err(_) if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Self::from_file(DEFAULT_CONFIG_FILE)?
+            }
+            Err(_) => Self::default(),
+        };
+
+        config.apply_env()?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    fn from_file(path: &str) -> Result<Self, ConfigError> {
+        let content = fs::read_to_string(path).map_err(|source| {
+            ConfigError::Read { path: path.to_string(), source }
+        })?;
+
+        toml::from_str(&content).map_err(|source| ConfigError::Parse {
+            path: path.to_string(),
+            source,
+        })
+    }
+
+    fn apply_env(&mut self) -> Result<(), ConfigError> {
+        env_override("SERVER_HOST", &mut self.server.host)?;
+        env_override("SERVER_PORT", &mut self.server.port)?;
+        env_override_list("TRUSTED_PROXIES", &mut self.server.trusted_proxies)?;
+        env_override("DATABASE_URL", &mut self.database.url)?;
+        env_override(
+            "DATABASE_MAX_CONNECTIONS",
+            &mut self.database.max_connections,
+        )?;
+        env_override("JWT_SECRET", &mut self.jwt.secret)?;
+        env_override("JWT_SIGNING_KID", &mut self.jwt.signing_kid)?;
+        env_override(
+            "REFRESH_TOKEN_HMAC_KEY",
+            &mut self.jwt.refresh_token_hmac_key,
+        )?;
+        env_override(
+            "ACCESS_TOKEN_TTL_SECS",
+            &mut self.jwt.access_token_ttl_secs,
+        )?;
+        env_override(
+            "REFRESH_TOKEN_TTL_SECS",
+            &mut self.jwt.refresh_token_ttl_secs,
+        )?;
+        env_override(
+            "DENYLIST_REFRESH_SECS",
+            &mut self.jwt.denylist_refresh_secs,
+        )?;
+        env_override("JWT_ISSUER", &mut self.jwt.issuer)?;
+        env_override("JWT_LEEWAY_SECS", &mut self.jwt.leeway_secs)?;
+        env_override("REFRESH_GRACE_SECS", &mut self.jwt.refresh_grace_secs)?;
+        env_override("ARGON2_MEMORY_COST", &mut self.password.memory_cost)?;
+        env_override("ARGON2_TIME_COST", &mut self.password.time_cost)?;
+        env_override("ARGON2_PARALLELISM", &mut self.password.parallelism)?;
+        env_override(
+            "PASSWORD_MIN_ENTROPY_BITS",
+            &mut self.password_policy.min_entropy_bits,
+        )?;
+        env_override_opt(
+            "BREACHED_PASSWORDS_PATH",
+            &mut self.password_policy.breached_passwords_path,
+        );
+        env_override("MFA_ISSUER", &mut self.mfa.issuer)?;
+        env_override("MAIL_TRANSPORT", &mut self.mail.transport)?;
+        env_override("MAIL_FROM", &mut self.mail.from)?;
+        env_override("SMTP_HOST", &mut self.mail.smtp_host)?;
+        env_override("SMTP_PORT", &mut self.mail.smtp_port)?;
+        env_override_opt("SMTP_USERNAME", &mut self.mail.smtp_username);
+        env_override_opt("SMTP_PASSWORD", &mut self.mail.smtp_password);
+        env_override("PUBLIC_URL", &mut self.account.public_url)?;
+        env_override("COOKIE_AUTH_ENABLED", &mut self.cookies.enabled)?;
+        env_override("COOKIE_SECURE", &mut self.cookies.secure)?;
+        env_override(
+            "LOGIN_MAX_FAILURES",
+            &mut self.login_throttle.max_failures,
+        )?;
+        env_override(
+            "LOGIN_IP_MAX_FAILURES",
+            &mut self.login_throttle.ip_max_failures,
+        )?;
+        env_override(
+            "LOGIN_LOCKOUT_SECS",
+            &mut self.login_throttle.lockout_secs,
+        )?;
+        env_override("SCHEDULER_ENABLED", &mut self.scheduler.enabled)?;
+        env_override(
+            "SCHEDULER_INTERVAL_SECS",
+            &mut self.scheduler.interval_secs,
+        )?;
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.database.url.is_empty() {
+            return Err(invalid("database.url", "must be set"));
+        }
+        if self.database.max_connections == 0 {
+            return Err(invalid(
+                "database.max_connections",
+                "must be greater than 0",
+            ));
+        }
+        self.jwt.validate()?;
+        if let Err(e) = self.password.params() {
+            return Err(invalid("password", e.to_string()));
+        }
+        self.password_policy.validate()?;
+        self.login_throttle.validate()?;
+        self.mfa.validate()?;
+        self.account.validate()?;
+        self.cookies.validate()?;
+        self.scheduler.validate()?;
+
+        Ok(())
+    }
+}
+
+fn env_override<T: FromStr>(
+    name: &str,
+    target: &mut T,
+) -> Result<(), ConfigError> {
+    let Ok(value) = env::var(name) else {
+        return Ok(());
+    };
+
+    *target = value.parse().map_err(|_| ConfigError::InvalidEnv {
+        name: name.to_string(),
+        value,
+    })?;
+
+    Ok(())
+}
+
+/// Comma-separated list, an empty value clears it.
+fn env_override_list<T: FromStr>(
+    name: &str,
+    target: &mut Vec<T>,
+) -> Result<(), ConfigError> {
+    let Ok(value) = env::var(name) else {
+        return Ok(());
+    };
+
+    *target = value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            item.parse().map_err(|_| ConfigError::InvalidEnv {
+                name: name.to_string(),
+                value: value.clone(),
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(())
+}
+
+fn env_override_opt(name: &str, target: &mut Option<String>) {
+    if let Ok(value) = env::var(name) {
+        *target = Some(value);
+    }
+}
+
+fn invalid(field: &'static str, reason: impl Into<String>) -> ConfigError {
+    ConfigError::Invalid { field, reason: reason.into() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jwt_config() -> JwtConfig {
+        JwtConfig {
+            secret: "s".repeat(MIN_SECRET_LEN),
+            refresh_token_hmac_key: "k".repeat(MIN_SECRET_LEN),
+            ..JwtConfig::default()
+        }
+    }
+
+    fn invalid_field(config: &JwtConfig) -> &'static str {
+        match config.validate() {
+            Err(ConfigError::Invalid { field, .. }) => field,
+            other => panic!("expected an invalid field, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn jwt_defaults_with_secrets_are_valid() {
+        assert!(jwt_config().validate().is_ok());
+    }
+
+    #[test]
+    fn jwt_rejects_short_secrets() {
+        let config = JwtConfig { secret: "short".to_string(), ..jwt_config() };
+        assert_eq!(invalid_field(&config), "jwt.secret");
+
+        let config = JwtConfig {
+            refresh_token_hmac_key: "k".repeat(MIN_SECRET_LEN - 1),
+            ..jwt_config()
+        };
+        assert_eq!(invalid_field(&config), "jwt.refresh_token_hmac_key");
+    }
+
+    #[test]
+    fn jwt_rejects_invalid_ttls() {
+        let config = JwtConfig { access_token_ttl_secs: 0, ..jwt_config() };
+        assert_eq!(invalid_field(&config), "jwt.access_token_ttl_secs");
+
+        let config = JwtConfig {
+            access_token_ttl_secs: 600,
+            refresh_token_ttl_secs: 600,
+            ..jwt_config()
+        };
+        assert_eq!(invalid_field(&config), "jwt.refresh_token_ttl_secs");
+
+        let config = JwtConfig { denylist_refresh_secs: 0, ..jwt_config() };
+        assert_eq!(invalid_field(&config), "jwt.denylist_refresh_secs");
+    }
+
+    #[test]
+    fn jwt_rejects_empty_issuer_and_audiences() {
+        let config = JwtConfig { issuer: String::new(), ..jwt_config() };
+        assert_eq!(invalid_field(&config), "jwt.issuer");
+
+        let config = JwtConfig { audiences: Vec::new(), ..jwt_config() };
+        assert_eq!(invalid_field(&config), "jwt.audiences");
+
+        let config = JwtConfig {
+            audiences: vec!["api".to_string(), String::new()],
+            ..jwt_config()
+        };
+        assert_eq!(invalid_field(&config), "jwt.audiences");
+    }
+
+    #[test]
+    fn jwt_leeway_must_be_shorter_than_access_ttl() {
+        let config = JwtConfig {
+            access_token_ttl_secs: 60,
+            refresh_grace_secs: 0,
+            leeway_secs: 60,
+            ..jwt_config()
+        };
+        assert_eq!(invalid_field(&config), "jwt.leeway_secs");
+
+        let config = JwtConfig { leeway_secs: 59, ..config };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn jwt_validation_accepts_every_issuer_and_audience() {
+        let config = JwtConfig {
+            accepted_issuers: vec!["old_issuer".to_string()],
+            audiences: vec!["api".to_string(), "admin".to_string()],
+            ..jwt_config()
+        };
+        let validation = config.validation();
+
+        let issuers = validation.iss.unwrap();
+        assert!(issuers.contains("actix_jwt_auth"));
+        assert!(issuers.contains("old_issuer"));
+        assert_eq!(validation.aud.unwrap().len(), 2);
+        assert!(validation.validate_nbf);
+        assert_eq!(validation.leeway, config.leeway_secs);
+        assert_eq!(config.default_audience(), "api");
+    }
+
+    #[test]
+    fn username_lock_doubles_per_failure() {
+        let config = LoginThrottleConfig {
+            backoff_base_secs: 2,
+            ..LoginThrottleConfig::default()
+        };
+
+        assert_eq!(config.username_lock(1), Duration::seconds(2));
+        assert_eq!(config.username_lock(2), Duration::seconds(4));
+        assert_eq!(config.username_lock(4), Duration::seconds(16));
+    }
+
+    #[test]
+    fn username_lock_is_capped_by_lockout() {
+        let config = LoginThrottleConfig {
+            max_failures: 100,
+            backoff_base_secs: 60,
+            lockout_secs: 300,
+            ..LoginThrottleConfig::default()
+        };
+
+        assert_eq!(config.username_lock(3), Duration::seconds(240));
+        assert_eq!(config.username_lock(4), Duration::seconds(300));
+        // Большие степени не переполняются
+        assert_eq!(config.username_lock(99), Duration::seconds(300));
+    }
+
+    #[test]
+    fn username_lock_uses_lockout_after_max_failures() {
+        let config = LoginThrottleConfig::default();
+
+        assert_eq!(
+            config.username_lock(config.max_failures),
+            Duration::seconds(config.lockout_secs)
+        );
+        assert_eq!(config.username_lock(0), Duration::seconds(1));
+    }
+}
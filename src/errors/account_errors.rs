@@ -0,0 +1,116 @@
+use actix_web::{HttpResponse, ResponseError};
+use argon2::password_hash::Error as PasswordHashError;
+use serde_json::json;
+use sqlx::Error as SqlxError;
+use thiserror::Error;
+use validator::ValidationErrors;
+
+use crate::errors::{
+    mail_errors::MailError, password_policy_errors::PasswordPolicyError,
+    users_errors::UserError,
+};
+
+#[derive(Debug, Error)]
+pub enum AccountError {
+    #[error("Validation error: {0}")]
+    Validation(#[from] ValidationErrors),
+
+    #[error("Database error: {0}")]
+    Database(#[from] SqlxError),
+
+    #[error(transparent)]
+    User(#[from] UserError),
+
+    #[error("Password hashing error: {0}")]
+    PasswordHash(#[from] PasswordHashError),
+
+    #[error(transparent)]
+    PasswordPolicy(#[from] PasswordPolicyError),
+
+    #[error("Mail error: {0}")]
+    Mail(#[from] MailError),
+
+    #[error("Invalid or expired token")]
+    InvalidToken,
+
+    #[error("User has no email address")]
+    NoEmail,
+
+    #[error("Current password is incorrect")]
+    WrongPassword,
+}
+
+impl ResponseError for AccountError {
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            AccountError::Validation(errors) => {
+                let details: Vec<String> = errors
+                    .field_errors()
+                    .iter()
+                    .flat_map(|(field, errors)| {
+                        errors.iter().map(move |e| {
+                            format!(
+                                "{}: {}",
+                                field,
+                                e.message.as_deref().unwrap_or("invalid")
+                            )
+                        })
+                    })
+                    .collect();
+                HttpResponse::BadRequest().json(json!({
+                    "error": "validation_failed",
+                    "message": "Validation failed",
+                    "details": details
+                }))
+            }
+
+            AccountError::Database(e) => {
+                log::error!("Database error: {e}");
+                HttpResponse::InternalServerError().json(json!({
+                    "error": "database_error",
+                    "message": "Database operation failed"
+                }))
+            }
+
+            AccountError::User(e) => e.error_response(),
+
+            AccountError::PasswordHash(e) => {
+                log::error!("Password hashing error: {e}");
+                HttpResponse::InternalServerError().json(json!({
+                    "error": "password_hash_error",
+                    "message": "Failed to process password"
+                }))
+            }
+
+            AccountError::PasswordPolicy(e) => e.error_response(),
+
+
+            AccountError::Mail(e) => {
+                log::error!("Mail error: {e}");
+                HttpResponse::InternalServerError().json(json!({
+                    "error": "mail_error",
+                    "message": "Failed to send mail"
+                }))
+            }
+
+            AccountError::InvalidToken => {
+                HttpResponse::BadRequest().json(json!({
+                    "error": "invalid_token",
+                    "message": "Token is invalid, expired or already used"
+                }))
+            }
+
+            AccountError::NoEmail => HttpResponse::BadRequest().json(json!({
+                "error": "no_email",
+                "message": "Set an email address first"
+            })),
+
+            AccountError::WrongPassword => {
+                HttpResponse::Forbidden().json(json!({
+                    "error": "wrong_password",
+                    "message": "Current password is incorrect"
+                }))
+            }
+        }
+    }
+}
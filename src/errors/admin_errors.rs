@@ -0,0 +1,76 @@
+use actix_web::{HttpResponse, ResponseError};
+use serde_json::json;
+use sqlx::Error as SqlxError;
+use thiserror::Error;
+use validator::ValidationErrors;
+
+use crate::errors::users_errors::UserError;
+
+#[derive(Debug, Error)]
+pub enum AdminError {
+    #[error("Validation error: {0}")]
+    Validation(#[from] ValidationErrors),
+
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] SqlxError),
+
+    #[error(transparent)]
+    User(#[from] UserError),
+}
+
+impl ResponseError for AdminError {
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            AdminError::Validation(errors) => {
+                let details: Vec<String> = errors
+                    .field_errors()
+                    .iter()
+                    .flat_map(|(field, errors)| {
+                        errors.iter().map(move |e| {
+                            format!(
+                                "{}: {}",
+                                field,
+                                e.message.as_deref().unwrap_or("invalid")
+                            )
+                        })
+                    })
+                    .collect();
+                HttpResponse::BadRequest().json(json!({
+                    "error": "validation_failed",
+                    "message": "Validation failed",
+                    "details": details
+                }))
+            }
+
+            AdminError::BadRequest(message) => {
+                HttpResponse::BadRequest().json(json!({
+                    "error": "bad_request",
+                    "message": message
+                }))
+            }
+
+            AdminError::NotFound(message) => {
+                HttpResponse::NotFound().json(json!({
+                    "error": "not_found",
+                    "message": message
+                }))
+            }
+
+            AdminError::Database(e) => {
+                log::error!("Database error: {e}");
+                HttpResponse::InternalServerError().json(json!({
+                    "error": "database_error",
+                    "message": "Database operation failed"
+                }))
+            }
+
+            AdminError::User(e) => e.error_response(),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use actix_web::{HttpResponse, ResponseError};
+use serde_json::json;
+use sqlx::Error as SqlxError;
+use thiserror::Error;
+use validator::ValidationErrors;
+
+#[derive(Debug, Error)]
+pub enum AuditError {
+    #[error("Validation error: {0}")]
+    Validation(#[from] ValidationErrors),
+
+    #[error("Database error: {0}")]
+    Database(#[from] SqlxError),
+}
+
+impl ResponseError for AuditError {
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            AuditError::Validation(errors) => {
+                let details: Vec<String> = errors
+                    .field_errors()
+                    .iter()
+                    .flat_map(|(field, errors)| {
+                        errors.iter().map(move |e| {
+                            format!(
+                                "{}: {}",
+                                field,
+                                e.message.as_deref().unwrap_or("invalid")
+                            )
+                        })
+                    })
+                    .collect();
+                HttpResponse::BadRequest().json(json!({
+                    "error": "validation_failed",
+                    "message": "Validation failed",
+                    "details": details
+                }))
+            }
+
+            AuditError::Database(e) => {
+                log::error!("Database error: {e}");
+                HttpResponse::InternalServerError().json(json!({
+                    "error": "database_error",
+                    "message": "Database operation failed"
+                }))
+            }
+        }
+    }
+}
@@ -1,4 +1,11 @@
-use actix_web::{HttpResponse, ResponseError};
+use actix_web::{
+    HttpResponse, ResponseError,
+    http::{
+        StatusCode,
+        header::{RETRY_AFTER, WWW_AUTHENTICATE},
+    },
+};
+use argon2::password_hash::Error as PasswordHashError;
 use jsonwebtoken::errors::Error as JwtError;
 use serde_json::json;
 use sqlx::Error as SqlxError;
@@ -17,113 +24,259 @@ pub enum AuthError {
     #[error("Authentication failed: {0}")]
     Authentication(String),
 
+    /// Unknown username or wrong password, deliberately not told apart.
+    #[error("Invalid username or password")]
+    InvalidCredentials,
+
     #[error("Token expired")]
     TokenExpired,
 
+    #[error("Token revoked")]
+    TokenRevoked,
+
+    #[error("Personal access token invalid, expired or revoked")]
+    PersonalTokenInvalid,
+
     #[error("Refresh token not found")]
     RefreshTokenNotFound,
 
+    #[error("Refresh token reused")]
+    RefreshTokenReused,
+
+    #[error("MFA challenge is invalid or expired")]
+    MfaChallengeInvalid,
+
+    #[error("Invalid MFA code")]
+    MfaInvalidCode,
+
+    #[error("Too many failed logins, retry after {retry_after_secs}s")]
+    LoginLocked { retry_after_secs: i64 },
+
+    #[error("Authentication required")]
+    Unauthenticated,
+
+    #[error("Missing or invalid CSRF token")]
+    CsrfTokenInvalid,
+
+    #[error("Role '{0}' required")]
+    MissingRole(&'static str),
+
+    #[error("Invalid scope '{0}'")]
+    InvalidScope(String),
+
+    #[error("Scope '{0}' required")]
+    InsufficientScope(&'static str),
+
+    #[error("Invalid audience '{0}'")]
+    InvalidAudience(String),
+
     // #[error("Unauthorized: {0}")]
     // Unauthorized(String),
 
     // #[error("Internal server error")]
     // InternalServerError,
-
     #[error("Validation error: {0}")]
     Validation(#[from] ValidationErrors),
 
     #[error("Database error: {0}")]
     Database(#[from] SqlxError),
+
+    #[error("Password hashing error: {0}")]
+    PasswordHash(#[from] PasswordHashError),
 }
 
-impl ResponseError for AuthError {
-    fn error_response(&self) -> HttpResponse {
+impl AuthError {
+    /// `error` and `message` of the JSON body, plus details for some
+    /// variants.
+    fn body(&self) -> serde_json::Value {
         match self {
-            AuthError::Validation(errors) => {
-                let details: Vec<String> = errors
-                    .field_errors()
-                    .iter()
-                    .flat_map(|(field, errors)| {
-                        errors.iter().map(move |e| {
-                            log::error!("Validatotion error, AuthError: {e}");
-                            format!(
-                                "{}: {}",
-                                field,
-                                e.message.as_deref().unwrap_or("invalid")
-                            )
-                        })
-                    })
-                    .collect();
-                HttpResponse::BadRequest().json(json!({
-                    "error": "validation_failed",
-                    "message": "Validation failed",
-                    "details": details
-                }))
-            }
-
-            AuthError::Authentication(message) => {
-                log::warn!("Authentication failed: {}", message);
-                HttpResponse::Unauthorized().json(json!({
-                    "error": "authentication_failed",
-                    "message": message
-                }))
-            }
+            AuthError::Validation(errors) => json!({
+                "error": "validation_failed",
+                "message": "Validation failed",
+                "details": Self::validation_details(errors)
+            }),
+            AuthError::Authentication(message) => json!({
+                "error": "authentication_failed",
+                "message": message
+            }),
+            AuthError::InvalidCredentials => json!({
+                "error": "invalid_credentials",
+                "message": "Invalid username or password"
+            }),
+            AuthError::InvalidToken(_) => json!({
+                "error": "invalid_token",
+                "message": "Invalid or malformed token"
+            }),
+            AuthError::TokenExpired => json!({
+                "error": "token_expired",
+                "message": "Token has expired"
+            }),
+            AuthError::TokenRevoked => json!({
+                "error": "token_revoked",
+                "message": "Token has been revoked"
+            }),
+            AuthError::PersonalTokenInvalid => json!({
+                "error": "invalid_token",
+                "message": "Personal access token is invalid, expired or revoked"
+            }),
+            AuthError::InvalidTime(_) => json!({
+                "error": "invalid_time",
+                "message": "Invalid timestamp operation"
+            }),
+            AuthError::RefreshTokenNotFound => json!({
+                "error": "refresh_token_not_found",
+                "message": "Refresh token not found or already expired"
+            }),
+            AuthError::RefreshTokenReused => json!({
+                "error": "refresh_token_reused",
+                "message": "Refresh token was already used, please log in again"
+            }),
+            AuthError::MfaChallengeInvalid => json!({
+                "error": "mfa_challenge_invalid",
+                "message": "MFA token is invalid or expired, please log in again"
+            }),
+            AuthError::MfaInvalidCode => json!({
+                "error": "mfa_invalid_code",
+                "message": "Invalid or already used code"
+            }),
+            AuthError::LoginLocked { retry_after_secs } => json!({
+                "error": "login_locked",
+                "message": "Too many failed login attempts, try again later",
+                "retry_after": retry_after_secs
+            }),
+            AuthError::Unauthenticated => json!({
+                "error": "unauthorized",
+                "message": "Authentication required"
+            }),
+            AuthError::CsrfTokenInvalid => json!({
+                "error": "csrf_token_invalid",
+                "message": "Missing or invalid CSRF token"
+            }),
+            AuthError::MissingRole(role) => json!({
+                "error": "forbidden",
+                "message": format!("Role '{role}' required")
+            }),
+            AuthError::InvalidScope(scope) => json!({
+                "error": "invalid_scope",
+                "message": format!("Scope '{scope}' is unknown or exceeds the granted scope")
+            }),
+            AuthError::InvalidAudience(audience) => json!({
+                "error": "invalid_audience",
+                "message": format!("Tokens cannot be issued for audience '{audience}'")
+            }),
+            AuthError::InsufficientScope(scope) => json!({
+                "error": "insufficient_scope",
+                "message": format!("Scope '{scope}' required")
+            }),
+            AuthError::Database(_) => json!({
+                "error": "database_error",
+                "message": "Database operation failed"
+            }),
+            AuthError::PasswordHash(_) => json!({
+                "error": "password_hash_error",
+                "message": "Failed to process password"
+            }),
+        }
+    }
 
-            AuthError::InvalidToken(e) => {
-                log::warn!("Invalid token: {}", e);
-                HttpResponse::Unauthorized().json(json!({
-                    "error": "invalid_token",
-                    "message": "Invalid or malformed token"
-                }))
-            }
+    fn validation_details(errors: &ValidationErrors) -> Vec<String> {
+        errors
+            .field_errors()
+            .iter()
+            .flat_map(|(field, errors)| {
+                errors.iter().map(move |e| {
+                    log::error!("Validatotion error, AuthError: {e}");
+                    format!(
+                        "{}: {}",
+                        field,
+                        e.message.as_deref().unwrap_or("invalid")
+                    )
+                })
+            })
+            .collect()
+    }
 
-            AuthError::TokenExpired => {
-                log::warn!("Token expired");
-                HttpResponse::Unauthorized().json(json!({
-                    "error": "token_expired",
-                    "message": "Token has expired"
-                }))
+    fn log(&self) {
+        match self {
+            AuthError::Authentication(message) => {
+                log::warn!("Authentication failed: {message}");
             }
-
-            AuthError::InvalidTime(e) => {
-                log::error!("Invalid timestamp: {}", e);
-                HttpResponse::InternalServerError().json(json!({
-                    "error": "invalid_time",
-                    "message": "Invalid timestamp operation"
-                }))
+            AuthError::InvalidToken(e) => log::warn!("Invalid token: {e}"),
+            AuthError::TokenExpired => log::warn!("Token expired"),
+            AuthError::TokenRevoked => log::warn!("Token revoked"),
+            AuthError::PersonalTokenInvalid => {
+                log::warn!("Invalid personal access token");
             }
-
+            AuthError::InvalidTime(e) => log::error!("Invalid timestamp: {e}"),
             AuthError::RefreshTokenNotFound => {
                 log::warn!("Refresh token not found");
-                HttpResponse::Unauthorized().json(json!({
-                    "error": "refresh_token_not_found",
-                    "message": "Refresh token not found or already expired"
-                }))
             }
+            AuthError::RefreshTokenReused => log::warn!("Refresh token reused"),
+            AuthError::MfaChallengeInvalid => {
+                log::warn!("Invalid or expired MFA challenge");
+            }
+            AuthError::LoginLocked { retry_after_secs } => {
+                log::warn!("Login locked for {retry_after_secs}s");
+            }
+            AuthError::CsrfTokenInvalid => {
+                log::warn!("Cookie request without a valid CSRF token");
+            }
+            AuthError::MissingRole(role) => {
+                log::warn!("Request without required role '{role}'");
+            }
+            AuthError::InsufficientScope(scope) => {
+                log::warn!("Request without required scope '{scope}'");
+            }
+            AuthError::Database(e) => log::error!("Database error: {e}"),
+            AuthError::PasswordHash(e) => {
+                log::error!("Password hashing error: {e}");
+            }
+            _ => {}
+        }
+    }
+}
+
+impl ResponseError for AuthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthError::Validation(_)
+            | AuthError::InvalidScope(_)
+            | AuthError::InvalidAudience(_) => StatusCode::BAD_REQUEST,
+            AuthError::LoginLocked { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AuthError::CsrfTokenInvalid
+            | AuthError::MissingRole(_)
+            | AuthError::InsufficientScope(_) => StatusCode::FORBIDDEN,
+            AuthError::InvalidTime(_)
+            | AuthError::Database(_)
+            | AuthError::PasswordHash(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
 
-            // AuthError::Unauthorized(message) => {
-            //     log::warn!("Unauthorized: {}", message);
-            //     HttpResponse::Unauthorized().json(json!({
-            //         "error": "unauthorized",
-            //         "message": message
-            //     }))
-            // }
-
-            // AuthError::InternalServerError => {
-            //     log::error!("Internal server error");
-            //     HttpResponse::InternalServerError().json(json!({
-            //         "error": "internal_server_error",
-            //         "message": "Something went wrong"
-            //     }))
-            // }
-
-            AuthError::Database(e) => {
-                log::error!("Database error: {e}");
-                HttpResponse::InternalServerError().json(json!({
-                    "error": "database_error",
-                    "message": "Database operation failed"
-                }))
+    fn error_response(&self) -> HttpResponse {
+        self.log();
+
+        let mut response = HttpResponse::build(self.status_code());
+        match self {
+            AuthError::LoginLocked { retry_after_secs } => {
+                response
+                    .insert_header((RETRY_AFTER, retry_after_secs.to_string()));
             }
+            AuthError::Unauthenticated => {
+                response.insert_header((WWW_AUTHENTICATE, "Bearer"));
+            }
+            // RFC 6750, section 3.1
+            AuthError::InsufficientScope(scope) => {
+                response.insert_header((
+                    WWW_AUTHENTICATE,
+                    format!(
+                        r#"Bearer error="insufficient_scope", scope="{scope}""#
+                    ),
+                ));
+            }
+            _ => {}
         }
+
+        response.json(self.body())
     }
 }
@@ -0,0 +1,19 @@
+use std::io::Error as IoError;
+
+use thiserror::Error;
+use toml::de::Error as TomlError;
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Failed to read config file {path}: {source}")]
+    Read { path: String, source: IoError },
+
+    #[error("Failed to parse config file {path}: {source}")]
+    Parse { path: String, source: TomlError },
+
+    #[error("Environment variable {name} has invalid value '{value}'")]
+    InvalidEnv { name: String, value: String },
+
+    #[error("Invalid config value {field}: {reason}")]
+    Invalid { field: &'static str, reason: String },
+}
@@ -0,0 +1,25 @@
+use std::io::Error as IoError;
+
+use lettre::{
+    address::AddressError, error::Error as MessageError,
+    transport::smtp::Error as SmtpError,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum MailError {
+    #[error("Invalid email address: {0}")]
+    Address(#[from] AddressError),
+
+    #[error("Failed to build message: {0}")]
+    Message(#[from] MessageError),
+
+    #[error("SMTP error: {0}")]
+    Smtp(#[from] SmtpError),
+
+    #[error("Failed to write mail file: {0}")]
+    Io(#[from] IoError),
+
+    #[error("Mail task failed: {0}")]
+    Blocking(String),
+}
@@ -0,0 +1,101 @@
+use actix_web::{HttpResponse, ResponseError};
+use serde_json::json;
+use sqlx::Error as SqlxError;
+use thiserror::Error;
+use validator::ValidationErrors;
+
+use crate::errors::auth_errors::AuthError;
+
+#[derive(Debug, Error)]
+pub enum MfaError {
+    #[error("Validation error: {0}")]
+    Validation(#[from] ValidationErrors),
+
+    #[error("Database error: {0}")]
+    Database(#[from] SqlxError),
+
+    #[error("Auth error: {0}")]
+    Auth(#[from] AuthError),
+
+    #[error("Unauthorized")]
+    Unauthorized,
+
+    #[error("Two-factor authentication is already enabled")]
+    AlreadyEnabled,
+
+    #[error("Two-factor authentication is not enrolled")]
+    NotEnrolled,
+
+    #[error("Two-factor authentication is not enabled")]
+    NotEnabled,
+
+    #[error("Invalid code")]
+    InvalidCode,
+}
+
+impl ResponseError for MfaError {
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            MfaError::Validation(errors) => {
+                let details: Vec<String> = errors
+                    .field_errors()
+                    .iter()
+                    .flat_map(|(field, errors)| {
+                        errors.iter().map(move |e| {
+                            format!(
+                                "{}: {}",
+                                field,
+                                e.message.as_deref().unwrap_or("invalid")
+                            )
+                        })
+                    })
+                    .collect();
+                HttpResponse::BadRequest().json(json!({
+                    "error": "validation_failed",
+                    "message": "Validation failed",
+                    "details": details
+                }))
+            }
+
+            MfaError::Database(e) => {
+                log::error!("Database error: {e}");
+                HttpResponse::InternalServerError().json(json!({
+                    "error": "database_error",
+                    "message": "Database operation failed"
+                }))
+            }
+
+            MfaError::Auth(e) => e.error_response(),
+
+            MfaError::Unauthorized => {
+                HttpResponse::Unauthorized().json(json!({
+                    "error": "unauthorized",
+                    "message": "Authentication required"
+                }))
+            }
+
+            MfaError::AlreadyEnabled => HttpResponse::Conflict().json(json!({
+                "error": "mfa_already_enabled",
+                "message": "Two-factor authentication is already enabled"
+            })),
+
+            MfaError::NotEnrolled => HttpResponse::BadRequest().json(json!({
+                "error": "mfa_not_enrolled",
+                "message": "Start enrollment before confirming it"
+            })),
+
+            MfaError::NotEnabled => HttpResponse::BadRequest().json(json!({
+                "error": "mfa_not_enabled",
+                "message": "Two-factor authentication is not enabled"
+            })),
+
+            MfaError::InvalidCode => {
+                log::warn!("Invalid two-factor code");
+                HttpResponse::BadRequest().json(json!({
+                    "error": "invalid_code",
+                    "message": "Invalid or already used code"
+                }))
+            }
+        }
+    }
+}
@@ -1,4 +1,13 @@
+pub mod account_errors;
+pub mod admin_errors;
+pub mod audit_errors;
 pub mod auth_errors;
+pub mod config_errors;
 pub mod cookies_errors;
+pub mod mail_errors;
+pub mod mfa_errors;
+pub mod password_policy_errors;
+pub mod personal_tokens_errors;
 pub mod posts_errors;
+pub mod sessions_errors;
 pub mod users_errors;
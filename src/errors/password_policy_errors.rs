@@ -0,0 +1,20 @@
+use actix_web::{HttpResponse, ResponseError};
+use serde_json::json;
+use thiserror::Error;
+
+use crate::models::password_policy_models::PolicyViolation;
+
+/// Every rule the password failed, not just the first.
+#[derive(Debug, Error)]
+#[error("Password violates the password policy: {0:?}")]
+pub struct PasswordPolicyError(pub Vec<PolicyViolation>);
+
+impl ResponseError for PasswordPolicyError {
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::BadRequest().json(json!({
+            "error": "password_policy_failed",
+            "message": "Password does not meet the password policy",
+            "details": self.0
+        }))
+    }
+}
@@ -0,0 +1,91 @@
+use actix_web::{HttpResponse, ResponseError};
+use serde_json::json;
+use sqlx::Error as SqlxError;
+use thiserror::Error;
+use validator::ValidationErrors;
+
+#[derive(Debug, Error)]
+pub enum PersonalTokenError {
+    #[error("Validation error: {0}")]
+    Validation(#[from] ValidationErrors),
+
+    #[error("Database error: {0}")]
+    Database(#[from] SqlxError),
+
+    #[error("Unknown scope '{0}'")]
+    UnknownScope(String),
+
+    #[error("Scope '{0}' exceeds the scope of the session")]
+    ScopeNotGranted(String),
+
+    #[error("Personal access tokens cannot create tokens")]
+    SessionRequired,
+
+    #[error("Personal access token not found")]
+    NotFound,
+}
+
+impl ResponseError for PersonalTokenError {
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            PersonalTokenError::Validation(errors) => {
+                let details: Vec<String> = errors
+                    .field_errors()
+                    .iter()
+                    .flat_map(|(field, errors)| {
+                        errors.iter().map(move |e| {
+                            format!(
+                                "{}: {}",
+                                field,
+                                e.message.as_deref().unwrap_or("invalid")
+                            )
+                        })
+                    })
+                    .collect();
+                HttpResponse::BadRequest().json(json!({
+                    "error": "validation_failed",
+                    "message": "Validation failed",
+                    "details": details
+                }))
+            }
+
+            PersonalTokenError::Database(e) => {
+                log::error!("Database error: {e}");
+                HttpResponse::InternalServerError().json(json!({
+                    "error": "database_error",
+                    "message": "Database operation failed"
+                }))
+            }
+
+            PersonalTokenError::UnknownScope(scope) => {
+                HttpResponse::BadRequest().json(json!({
+                    "error": "invalid_scope",
+                    "message": format!("Unknown scope '{scope}'")
+                }))
+            }
+
+            PersonalTokenError::ScopeNotGranted(scope) => {
+                HttpResponse::BadRequest().json(json!({
+                    "error": "invalid_scope",
+                    "message": format!(
+                        "Scope '{scope}' exceeds the scope of this session"
+                    )
+                }))
+            }
+
+            PersonalTokenError::SessionRequired => {
+                HttpResponse::Forbidden().json(json!({
+                    "error": "forbidden",
+                    "message": "Personal access tokens can only be created from a login session"
+                }))
+            }
+
+            PersonalTokenError::NotFound => {
+                HttpResponse::NotFound().json(json!({
+                    "error": "not_found",
+                    "message": "Personal access token not found"
+                }))
+            }
+        }
+    }
+}
@@ -58,7 +58,7 @@ impl ResponseError for PostError {
             })),
 
             PostError::Unauthorized(message) => {
-                log::warn!("Unauthorized: {}", message);
+                log::warn!("Unauthorized: {message}");
                 HttpResponse::Unauthorized().json(json!({
                     "error": "unauthorized",
                     "message": message
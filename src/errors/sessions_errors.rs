@@ -0,0 +1,32 @@
+use actix_web::{HttpResponse, ResponseError};
+use serde_json::json;
+use sqlx::Error as SqlxError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SessionError {
+    #[error("Database error: {0}")]
+    Database(#[from] SqlxError),
+
+    #[error("Session not found")]
+    NotFound,
+}
+
+impl ResponseError for SessionError {
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            SessionError::Database(e) => {
+                log::error!("Database error: {e}");
+                HttpResponse::InternalServerError().json(json!({
+                    "error": "database_error",
+                    "message": "Database operation failed"
+                }))
+            }
+
+            SessionError::NotFound => HttpResponse::NotFound().json(json!({
+                "error": "not_found",
+                "message": "Session not found"
+            })),
+        }
+    }
+}
@@ -1,9 +1,12 @@
 use actix_web::{HttpResponse, ResponseError};
+use argon2::password_hash::Error as PasswordHashError;
 use serde_json::json;
 use sqlx::Error as SqlxError;
 use thiserror::Error;
 use validator::ValidationErrors;
 
+use crate::errors::password_policy_errors::PasswordPolicyError;
+
 #[derive(Debug, Error)]
 pub enum UserError {
     #[error("Validation error: {0}")]
@@ -14,6 +17,15 @@ pub enum UserError {
 
     #[error("User not found")]
     NotFound,
+
+    #[error("Forbidden")]
+    Forbidden,
+
+    #[error("Password hashing error: {0}")]
+    PasswordHash(#[from] PasswordHashError),
+
+    #[error(transparent)]
+    PasswordPolicy(#[from] PasswordPolicyError),
 }
 
 impl ResponseError for UserError {
@@ -53,6 +65,24 @@ impl ResponseError for UserError {
                 "error": "not_found",
                 "message": "User not found"
             })),
+
+            UserError::Forbidden => {
+                log::warn!("User tried to change another account");
+                HttpResponse::Forbidden().json(json!({
+                    "error": "forbidden",
+                    "message": "You can only change your own account"
+                }))
+            }
+
+            UserError::PasswordHash(e) => {
+                log::error!("Password hashing error: {e}");
+                HttpResponse::InternalServerError().json(json!({
+                    "error": "password_hash_error",
+                    "message": "Failed to process password"
+                }))
+            }
+
+            UserError::PasswordPolicy(e) => e.error_response(),
         }
     }
 }
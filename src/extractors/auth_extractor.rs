@@ -0,0 +1,155 @@
+use std::{future::Future, pin::Pin};
+
+use actix_web::{
+    FromRequest, HttpMessage, HttpRequest,
+    dev::Payload,
+    http::header::{AUTHORIZATION, Header},
+    web::Data,
+};
+use actix_web_httpauth::headers::authorization::{Authorization, Bearer};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    config::app_config::AppConfig,
+    errors::auth_errors::AuthError,
+    models::auth_models::Claims,
+    services::{
+        auth_services::AuthService, cookie_services::CookieService,
+        denylist_services::TokenDenylist, key_ring_services::KeyRing,
+        scope_services::ScopeService, user_id_cache_services::UserIdCache,
+    },
+};
+
+/// The caller of a request with a valid access token. Rejects the request
+/// with 401 otherwise.
+///
+/// Behind the auth middleware the claims it validated are reused; on other
+/// routes the `Authorization` header or access token cookie is validated
+/// here.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub id: i32,
+    /// Public id of the user, the `sub` of the token.
+    pub public_id: Uuid,
+    pub roles: Vec<String>,
+    /// Space-separated scopes the token is limited to, `None` if it is not.
+    pub scope: Option<String>,
+    /// Session (refresh token family) of the access token.
+    pub session_id: Option<Uuid>,
+    /// `jti` of the access token.
+    #[allow(dead_code)]
+    pub token_id: Uuid,
+    #[allow(dead_code)]
+    pub issued_at: i64,
+    #[allow(dead_code)]
+    pub expires_at: i64,
+}
+
+/// Like [`AuthenticatedUser`], but requests without an access token pass
+/// as `None`. An invalid token is still rejected, so clients learn their
+/// token has expired.
+#[derive(Debug, Clone)]
+pub struct OptionalUser(pub Option<AuthenticatedUser>);
+
+impl AuthenticatedUser {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scope
+            .as_deref()
+            .is_none_or(|granted| ScopeService::contains(granted, scope))
+    }
+}
+
+impl From<Claims> for AuthenticatedUser {
+    fn from(claims: Claims) -> Self {
+        AuthenticatedUser {
+            id: claims.user_id,
+            public_id: claims.sub,
+            roles: claims.roles,
+            scope: claims.scope,
+            session_id: claims.sid,
+            token_id: claims.jti,
+            issued_at: claims.iat,
+            expires_at: claims.exp,
+        }
+    }
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = AuthError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move {
+            claims_from_request(&req)
+                .await?
+                .ok_or(AuthError::Unauthenticated)
+                .map(AuthenticatedUser::from)
+        })
+    }
+}
+
+impl FromRequest for OptionalUser {
+    type Error = AuthError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move {
+            let claims = claims_from_request(&req).await?;
+            Ok(OptionalUser(claims.map(AuthenticatedUser::from)))
+        })
+    }
+}
+
+/// Claims stored by the auth middleware, or else the validated claims of
+/// the `Authorization` header or the access token cookie. `None` if the
+/// request carries neither.
+async fn claims_from_request(
+    req: &HttpRequest,
+) -> Result<Option<Claims>, AuthError> {
+    if let Some(claims) = req.extensions().get::<Claims>() {
+        return Ok(Some(claims.clone()));
+    }
+
+    let (
+        Some(pool),
+        Some(config),
+        Some(key_ring),
+        Some(denylist),
+        Some(user_ids),
+    ) = (
+        req.app_data::<Data<PgPool>>(),
+        req.app_data::<Data<AppConfig>>(),
+        req.app_data::<Data<KeyRing>>(),
+        req.app_data::<Data<TokenDenylist>>(),
+        req.app_data::<Data<UserIdCache>>(),
+    )
+    else {
+        log::error!("Auth services are not registered in app data");
+        return Err(AuthError::Unauthenticated);
+    };
+
+    let token = match Authorization::<Bearer>::parse(req) {
+        Ok(authorization) => authorization.as_ref().token().to_string(),
+        Err(_) if req.headers().contains_key(AUTHORIZATION) => {
+            return Err(AuthError::Unauthenticated);
+        }
+        Err(_) => match CookieService::access_token(req, &config.cookies)? {
+            Some(token) => token,
+            None => return Ok(None),
+        },
+    };
+
+    let claims = AuthService::validate_bearer_token(
+        pool, config, key_ring, denylist, user_ids, &token,
+    )
+    .await?;
+    req.extensions_mut().insert(claims.clone());
+    Ok(Some(claims))
+}
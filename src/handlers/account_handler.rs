@@ -0,0 +1,156 @@
+use actix_web::{
+    HttpResponse, Result, post, rt,
+    web::{Data, Json, ServiceConfig, scope},
+};
+use actix_web_httpauth::middleware::HttpAuthentication;
+use serde_json::json;
+use sqlx::PgPool;
+use validator::Validate;
+
+use crate::{
+    config::app_config::AppConfig,
+    errors::account_errors::AccountError,
+    extractors::auth_extractor::AuthenticatedUser,
+    middlewares::scope_middleware::require_scope,
+    models::{
+        account_models::{
+            EmailVerificationConfirm, PasswordChangeRequest,
+            PasswordResetConfirm, PasswordResetRequest,
+        },
+        scope_models::{ACCOUNT, USERS_WRITE},
+    },
+    repositories::users_repository::UserRepository,
+    services::{
+        account_services::AccountService, denylist_services::TokenDenylist,
+        mailer_services::Mailer, password_policy_services::PasswordPolicy,
+    },
+};
+
+/// Always answers the same way. The lookup and the mail run in the
+/// background, so the response time does not reveal whether the address
+/// is registered either.
+#[post("/password-reset/request")]
+pub async fn request_password_reset(
+    reset_data: Json<PasswordResetRequest>,
+    pool: Data<PgPool>,
+    config: Data<AppConfig>,
+    mailer: Data<dyn Mailer>,
+) -> Result<HttpResponse, AccountError> {
+    reset_data.validate()?;
+
+    rt::spawn(async move {
+        if let Err(e) = AccountService::request_password_reset(
+            &pool,
+            &config,
+            mailer,
+            &reset_data.email,
+        )
+        .await
+        {
+            log::error!("Password reset request failed: {e}");
+        }
+    });
+
+    Ok(HttpResponse::Accepted().json(json!({
+        "message": "If the address is registered, a reset link has been sent"
+    })))
+}
+
+#[post("/password-reset/confirm")]
+pub async fn confirm_password_reset(
+    reset_data: Json<PasswordResetConfirm>,
+    pool: Data<PgPool>,
+    config: Data<AppConfig>,
+    policy: Data<PasswordPolicy>,
+    denylist: Data<TokenDenylist>,
+) -> Result<HttpResponse, AccountError> {
+    reset_data.validate()?;
+
+    AccountService::reset_password(
+        &pool,
+        &config,
+        &policy,
+        &denylist,
+        &reset_data,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(json!({
+        "message": "Password has been reset, please log in again"
+    })))
+}
+
+/// Needs the current password. Other sessions and personal access tokens
+/// are revoked; the caller's session stays, but has to refresh its access
+/// token.
+#[post("", wrap = "require_scope(USERS_WRITE)")]
+pub async fn change_password(
+    caller: AuthenticatedUser,
+    password_data: Json<PasswordChangeRequest>,
+    pool: Data<PgPool>,
+    config: Data<AppConfig>,
+    policy: Data<PasswordPolicy>,
+    denylist: Data<TokenDenylist>,
+) -> Result<HttpResponse, AccountError> {
+    password_data.validate()?;
+
+    let revoked = AccountService::change_password(
+        &pool,
+        &config,
+        &policy,
+        &denylist,
+        caller.id,
+        caller.session_id,
+        &password_data,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(json!({
+        "message": "Password has been changed",
+        "revoked": revoked
+    })))
+}
+
+#[post("")]
+pub async fn request_email_verification(
+    caller: AuthenticatedUser,
+    pool: Data<PgPool>,
+    config: Data<AppConfig>,
+    mailer: Data<dyn Mailer>,
+) -> Result<HttpResponse, AccountError> {
+    let user = UserRepository::find_by_id(&pool, caller.id).await?;
+
+    AccountService::send_email_verification(&pool, &config, mailer, &user)
+        .await?;
+    Ok(HttpResponse::Accepted().json(json!({
+        "message": "Verification link has been sent"
+    })))
+}
+
+#[post("/email-verification/confirm")]
+pub async fn confirm_email_verification(
+    verification_data: Json<EmailVerificationConfirm>,
+    pool: Data<PgPool>,
+    config: Data<AppConfig>,
+) -> Result<HttpResponse, AccountError> {
+    verification_data.validate()?;
+
+    AccountService::verify_email(&pool, &config, &verification_data.token)
+        .await?;
+    Ok(HttpResponse::Ok().json(json!({"message": "Email address verified"})))
+}
+
+pub fn account_routes(cfg: &mut ServiceConfig) {
+    let auth = HttpAuthentication::with_fn(
+        crate::middlewares::auth_middleware::auth_middleware_validator,
+    );
+
+    cfg.service(request_password_reset)
+        .service(confirm_password_reset)
+        .service(confirm_email_verification)
+        .service(
+            scope("/email-verification/request")
+                .wrap(require_scope(ACCOUNT))
+                .wrap(auth.clone())
+                .service(request_email_verification),
+        )
+        .service(scope("/me/password").wrap(auth).service(change_password));
+}
@@ -0,0 +1,154 @@
+use actix_web::{
+    HttpResponse, Result, delete, get, post, put,
+    web::{Data, Json, Path, Query, ServiceConfig, scope},
+};
+use actix_web_httpauth::middleware::HttpAuthentication;
+use sqlx::PgPool;
+use validator::Validate;
+
+use crate::{
+    errors::admin_errors::AdminError,
+    extractors::auth_extractor::AuthenticatedUser,
+    middlewares::{
+        role_middleware::require_role, scope_middleware::require_scope,
+    },
+    models::{
+        admin_models::{UnlockLoginRequest, UnlockLoginResponse},
+        audit_models::AuthEventsQuery,
+        role_models::{ADMIN_ROLE, UserRolePath},
+        scope_models::ADMIN,
+        users_models::UserPath,
+    },
+    repositories::{
+        audit_repository::AuditRepository, users_repository::UserRepository,
+    },
+    services::{
+        denylist_services::TokenDenylist,
+        login_throttle_services::LoginThrottleService,
+        roles_services::RolesService,
+    },
+};
+
+#[post("/unlock-login")]
+pub async fn unlock_login(
+    admin: AuthenticatedUser,
+    unlock_data: Json<UnlockLoginRequest>,
+    pool: Data<PgPool>,
+) -> Result<HttpResponse, AdminError> {
+    unlock_data.validate()?;
+    if unlock_data.username.is_none() && unlock_data.ip_address.is_none() {
+        return Err(AdminError::BadRequest(
+            "Either username or ip_address is required".to_string(),
+        ));
+    }
+
+    let unlocked = LoginThrottleService::unlock(
+        &pool,
+        unlock_data.username.as_deref(),
+        unlock_data.ip_address.as_deref(),
+    )
+    .await?;
+    log::info!(
+        "Admin {} unlocked login for {:?} / {:?}",
+        admin.id,
+        unlock_data.username,
+        unlock_data.ip_address
+    );
+
+    Ok(HttpResponse::Ok().json(UnlockLoginResponse { unlocked }))
+}
+
+#[get("/roles")]
+pub async fn get_roles(pool: Data<PgPool>) -> Result<HttpResponse, AdminError> {
+    let roles = RolesService::list(&pool).await?;
+    Ok(HttpResponse::Ok().json(roles))
+}
+
+#[get("/users/{user_id}/roles")]
+pub async fn get_user_roles(
+    path: Path<UserPath>,
+    pool: Data<PgPool>,
+) -> Result<HttpResponse, AdminError> {
+    let user = UserRepository::find(&pool, path.user_id).await?;
+    let roles = RolesService::find(&pool, &user).await?;
+    Ok(HttpResponse::Ok().json(roles))
+}
+
+#[put("/users/{user_id}/roles/{role}")]
+pub async fn grant_role(
+    admin: AuthenticatedUser,
+    path: Path<UserRolePath>,
+    pool: Data<PgPool>,
+) -> Result<HttpResponse, AdminError> {
+    path.validate()?;
+
+    let user = UserRepository::find(&pool, path.user_id).await?;
+    let roles = RolesService::grant(&pool, &user, &path.role).await?;
+    log::info!(
+        "Admin {} granted role '{}' to user {}",
+        admin.id,
+        path.role,
+        user.id
+    );
+    Ok(HttpResponse::Ok().json(roles))
+}
+
+#[delete("/users/{user_id}/roles/{role}")]
+pub async fn revoke_role(
+    admin: AuthenticatedUser,
+    path: Path<UserRolePath>,
+    pool: Data<PgPool>,
+    denylist: Data<TokenDenylist>,
+) -> Result<HttpResponse, AdminError> {
+    path.validate()?;
+    let user = UserRepository::find(&pool, path.user_id).await?;
+    // Иначе можно остаться без единого администратора
+    if user.id == admin.id && path.role == ADMIN_ROLE {
+        return Err(AdminError::BadRequest(
+            "Admins cannot revoke their own admin role".to_string(),
+        ));
+    }
+
+    let roles =
+        RolesService::revoke(&pool, &denylist, &user, &path.role).await?;
+    log::info!(
+        "Admin {} revoked role '{}' from user {}",
+        admin.id,
+        path.role,
+        user.id
+    );
+    Ok(HttpResponse::Ok().json(roles))
+}
+
+/// Searches the audit trail. Every filter is optional; results are newest
+/// first, paged with `limit` and `before_id`.
+#[get("/auth-events")]
+pub async fn get_auth_events(
+    query: Query<AuthEventsQuery>,
+    pool: Data<PgPool>,
+) -> Result<HttpResponse, AdminError> {
+    query.validate()?;
+
+    let events = AuditRepository::search(&pool, &query).await?;
+    Ok(HttpResponse::Ok().json(events))
+}
+
+pub fn admin_routes(cfg: &mut ServiceConfig) {
+    let auth = HttpAuthentication::with_fn(
+        crate::middlewares::auth_middleware::auth_middleware_validator,
+    );
+
+    // Последний wrap выполняется первым: сначала токен, потом scope и роль
+    cfg.service(
+        scope("/admin")
+            .wrap(require_role(ADMIN_ROLE))
+            .wrap(require_scope(ADMIN))
+            .wrap(auth)
+            .service(unlock_login)
+            .service(get_roles)
+            .service(get_user_roles)
+            .service(grant_role)
+            .service(revoke_role)
+            .service(get_auth_events),
+    );
+}
@@ -0,0 +1,48 @@
+use actix_web::{
+    HttpResponse, Result, get,
+    web::{Data, Query, ServiceConfig, scope},
+};
+use actix_web_httpauth::middleware::HttpAuthentication;
+use sqlx::PgPool;
+use validator::Validate;
+
+use crate::{
+    errors::audit_errors::AuditError,
+    extractors::auth_extractor::AuthenticatedUser,
+    middlewares::scope_middleware::require_scope,
+    models::{audit_models::SecurityEventsQuery, scope_models::ACCOUNT},
+    repositories::audit_repository::AuditRepository,
+};
+
+/// Logins, refreshes and logouts of the caller's account, newest first.
+/// Failed logins for the caller's username are included.
+#[get("")]
+pub async fn get_security_events(
+    user: AuthenticatedUser,
+    query: Query<SecurityEventsQuery>,
+    pool: Data<PgPool>,
+) -> Result<HttpResponse, AuditError> {
+    query.validate()?;
+
+    let events = AuditRepository::find_by_user(
+        &pool,
+        user.id,
+        query.limit,
+        query.before_id,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(events))
+}
+
+pub fn audit_routes(cfg: &mut ServiceConfig) {
+    let auth = HttpAuthentication::with_fn(
+        crate::middlewares::auth_middleware::auth_middleware_validator,
+    );
+
+    cfg.service(
+        scope("/me/security-events")
+            .wrap(require_scope(ACCOUNT))
+            .wrap(auth)
+            .service(get_security_events),
+    );
+}
@@ -1,51 +1,181 @@
 use actix_web::{
-    HttpResponse, Result, post,
+    HttpRequest, HttpResponse, Result, post,
     web::{Data, Json, ServiceConfig},
 };
+use actix_web_httpauth::extractors::bearer::BearerAuth;
 use sqlx::PgPool;
 use validator::Validate;
 
 use crate::{
+    config::app_config::AppConfig,
     errors::auth_errors::AuthError,
-    models::auth_models::{LoginRequest, RefreshRequest},
-    services::auth_services::AuthService,
+    models::{
+        auth_models::{
+            ClientInfo, CookieTokens, LoginRequest, LoginResponse,
+            RefreshRequest, TokenPair,
+        },
+        mfa_models::MfaLoginRequest,
+    },
+    services::{
+        auth_services::AuthService, cookie_services::CookieService,
+        denylist_services::TokenDenylist, key_ring_services::KeyRing,
+    },
 };
 
+/// Returns the tokens in the body, or in cookie mode sets them as cookies
+/// together with a new CSRF token.
+fn token_response(config: &AppConfig, token_pair: TokenPair) -> HttpResponse {
+    if !config.cookies.enabled {
+        return HttpResponse::Ok().json(token_pair);
+    }
+
+    let csrf_token = CookieService::generate_csrf_token();
+    let mut response = HttpResponse::Ok();
+    for cookie in CookieService::token_cookies(config, &token_pair, &csrf_token)
+    {
+        response.cookie(cookie);
+    }
+
+    let access_token =
+        (!config.cookies.access_token).then_some(token_pair.access_token);
+    response.json(CookieTokens { access_token, csrf_token })
+}
+
+/// The refresh token from the body or, in cookie mode, from the cookie.
+/// A cookie is sent by the browser on its own, so it needs the CSRF token.
+fn refresh_request(
+    req: &HttpRequest,
+    config: &AppConfig,
+    body: Option<Json<RefreshRequest>>,
+) -> Result<Option<RefreshRequest>, AuthError> {
+    if let Some(body) = body {
+        body.validate()?;
+        return Ok(Some(body.into_inner()));
+    }
+    if !config.cookies.enabled {
+        return Ok(None);
+    }
+
+    let Some(refresh_token) = CookieService::refresh_token(req) else {
+        return Ok(None);
+    };
+    CookieService::verify_csrf(req)?;
+    Ok(Some(RefreshRequest { refresh_token, scope: None }))
+}
+
 #[post("/login")]
 pub async fn login(
+    req: HttpRequest,
     credentials: Json<LoginRequest>,
     pool: Data<PgPool>,
+    config: Data<AppConfig>,
+    key_ring: Data<KeyRing>,
 ) -> Result<HttpResponse, AuthError> {
     credentials.validate().map_err(AuthError::Validation)?;
 
-    let token_pair =
-        AuthService::login(&pool, credentials.into_inner()).await?;
-    Ok(HttpResponse::Ok().json(token_pair))
+    let response = AuthService::login(
+        &pool,
+        &config,
+        &key_ring,
+        credentials.into_inner(),
+        &ClientInfo::from_request(&req, &config.server),
+    )
+    .await?;
+    match response {
+        LoginResponse::Tokens(token_pair) => {
+            Ok(token_response(&config, token_pair))
+        }
+        LoginResponse::MfaRequired(challenge) => {
+            Ok(HttpResponse::Ok().json(challenge))
+        }
+    }
+}
+
+#[post("/login/mfa")]
+pub async fn login_mfa(
+    req: HttpRequest,
+    mfa_data: Json<MfaLoginRequest>,
+    pool: Data<PgPool>,
+    config: Data<AppConfig>,
+    key_ring: Data<KeyRing>,
+) -> Result<HttpResponse, AuthError> {
+    mfa_data.validate().map_err(AuthError::Validation)?;
+
+    let token_pair = AuthService::login_mfa(
+        &pool,
+        &config,
+        &key_ring,
+        mfa_data.into_inner(),
+        &ClientInfo::from_request(&req, &config.server),
+    )
+    .await?;
+    Ok(token_response(&config, token_pair))
 }
 
 #[post("/refresh")]
 pub async fn refresh(
-    token_data: Json<RefreshRequest>,
+    req: HttpRequest,
+    token_data: Option<Json<RefreshRequest>>,
     pool: Data<PgPool>,
+    config: Data<AppConfig>,
+    key_ring: Data<KeyRing>,
 ) -> Result<HttpResponse, AuthError> {
-    token_data.validate().map_err(AuthError::Validation)?;
+    let token_data = refresh_request(&req, &config, token_data)?
+        .ok_or(AuthError::RefreshTokenNotFound)?;
 
-    let token_pair =
-        AuthService::refresh(&pool, token_data.into_inner()).await?;
-    Ok(HttpResponse::Ok().json(token_pair))
+    let token_pair = AuthService::refresh(
+        &pool,
+        &config,
+        &key_ring,
+        token_data,
+        &ClientInfo::from_request(&req, &config.server),
+    )
+    .await?;
+    Ok(token_response(&config, token_pair))
 }
 
+/// Revokes the refresh token and, when an access token is sent as bearer
+/// token or cookie, that token and its whole session. The refresh cookie is
+/// scoped to `/refresh`, so cookie clients log out with the access token.
 #[post("/logout")]
 pub async fn logout(
-    token_data: Json<RefreshRequest>,
+    req: HttpRequest,
+    token_data: Option<Json<RefreshRequest>>,
+    bearer: Option<BearerAuth>,
     pool: Data<PgPool>,
+    config: Data<AppConfig>,
+    key_ring: Data<KeyRing>,
+    denylist: Data<TokenDenylist>,
 ) -> Result<HttpResponse, AuthError> {
-    token_data.validate().map_err(AuthError::Validation)?;
+    let token_data = refresh_request(&req, &config, token_data)?;
+    let access_token = match bearer {
+        Some(bearer) => Some(bearer.token().to_string()),
+        None => CookieService::access_token(&req, &config.cookies)?,
+    };
+    if token_data.is_none() && access_token.is_none() {
+        return Err(AuthError::Unauthenticated);
+    }
+
+    AuthService::logout(
+        &pool,
+        &config,
+        &key_ring,
+        &denylist,
+        token_data,
+        access_token.as_deref(),
+        &ClientInfo::from_request(&req, &config.server),
+    )
+    .await?;
 
-    AuthService::logout(&pool, token_data.into_inner()).await?;
-    Ok(HttpResponse::Ok().json("Logged out successfully"))
+    let mut response = HttpResponse::Ok();
+    if config.cookies.enabled {
+        for cookie in CookieService::removal_cookies(&config.cookies) {
+            response.cookie(cookie);
+        }
+    }
+    Ok(response.json("Logged out successfully"))
 }
 
 pub fn auth_routes(cfg: &mut ServiceConfig) {
-    cfg.service(login).service(refresh).service(logout);
+    cfg.service(login).service(login_mfa).service(refresh).service(logout);
 }
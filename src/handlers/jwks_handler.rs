@@ -0,0 +1,22 @@
+use crate::services::key_ring_services::KeyRing;
+use actix_web::{
+    HttpResponse, Result, get,
+    http::header::{CacheControl, CacheDirective},
+    web::{Data, ServiceConfig},
+};
+
+/// Public keys other services use to verify access tokens, including
+/// retired keys whose tokens have not expired yet.
+#[get("/.well-known/jwks.json")]
+pub async fn get_jwks(key_ring: Data<KeyRing>) -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok()
+        .insert_header(CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(300),
+        ]))
+        .json(key_ring.jwks()))
+}
+
+pub fn jwks_routes(cfg: &mut ServiceConfig) {
+    cfg.service(get_jwks);
+}
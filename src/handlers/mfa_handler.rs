@@ -0,0 +1,89 @@
+use actix_web::{
+    HttpRequest, HttpResponse, Result, post,
+    web::{Data, Json, ServiceConfig, scope},
+};
+use actix_web_httpauth::middleware::HttpAuthentication;
+use serde_json::json;
+use sqlx::PgPool;
+use validator::Validate;
+
+use crate::{
+    config::app_config::AppConfig,
+    errors::mfa_errors::MfaError,
+    extractors::auth_extractor::AuthenticatedUser,
+    middlewares::scope_middleware::require_scope,
+    models::{
+        auth_models::ClientInfo, mfa_models::MfaCodeRequest,
+        scope_models::ACCOUNT,
+    },
+    services::mfa_services::MfaService,
+};
+
+#[post("/enroll")]
+pub async fn enroll(
+    user: AuthenticatedUser,
+    pool: Data<PgPool>,
+    config: Data<AppConfig>,
+) -> Result<HttpResponse, MfaError> {
+    let enrollment = MfaService::enroll(&pool, &config, user.id).await?;
+    Ok(HttpResponse::Ok().json(enrollment))
+}
+
+#[post("/confirm")]
+pub async fn confirm(
+    req: HttpRequest,
+    user: AuthenticatedUser,
+    code_data: Json<MfaCodeRequest>,
+    pool: Data<PgPool>,
+    config: Data<AppConfig>,
+) -> Result<HttpResponse, MfaError> {
+    code_data.validate()?;
+
+    let recovery_codes = MfaService::confirm(
+        &pool,
+        &config,
+        user.id,
+        &code_data.code,
+        &ClientInfo::from_request(&req, &config.server),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(recovery_codes))
+}
+
+#[post("/disable")]
+pub async fn disable(
+    req: HttpRequest,
+    user: AuthenticatedUser,
+    code_data: Json<MfaCodeRequest>,
+    pool: Data<PgPool>,
+    config: Data<AppConfig>,
+) -> Result<HttpResponse, MfaError> {
+    code_data.validate()?;
+
+    MfaService::disable(
+        &pool,
+        &config,
+        user.id,
+        &code_data.code,
+        &ClientInfo::from_request(&req, &config.server),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(json!({
+        "message": "Two-factor authentication disabled"
+    })))
+}
+
+pub fn mfa_routes(cfg: &mut ServiceConfig) {
+    let auth = HttpAuthentication::with_fn(
+        crate::middlewares::auth_middleware::auth_middleware_validator,
+    );
+
+    cfg.service(
+        scope("/me/mfa")
+            .wrap(require_scope(ACCOUNT))
+            .wrap(auth)
+            .service(enroll)
+            .service(confirm)
+            .service(disable),
+    );
+}
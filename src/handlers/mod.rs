@@ -1,5 +1,12 @@
+pub mod account_handler;
+pub mod admin_handler;
+pub mod audit_handler;
 pub mod auth_handler;
 pub mod cookies_handler;
+pub mod jwks_handler;
+pub mod mfa_handler;
+pub mod personal_tokens_handler;
 pub mod ping_pong_handler;
 pub mod posts_handler;
+pub mod sessions_handler;
 pub mod users_handler;
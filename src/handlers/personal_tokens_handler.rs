@@ -0,0 +1,84 @@
+use actix_web::{
+    HttpResponse, Result, delete, get, post,
+    web::{Data, Json, Path, ServiceConfig, scope},
+};
+use actix_web_httpauth::middleware::HttpAuthentication;
+use serde_json::json;
+use sqlx::PgPool;
+use validator::Validate;
+
+use crate::{
+    config::app_config::AppConfig,
+    errors::personal_tokens_errors::PersonalTokenError,
+    extractors::auth_extractor::AuthenticatedUser,
+    middlewares::scope_middleware::require_scope,
+    models::{
+        personal_tokens_models::{CreatePersonalToken, PersonalTokenPath},
+        scope_models::ACCOUNT,
+    },
+    repositories::personal_tokens_repository::PersonalTokensRepository,
+    services::personal_tokens_services::PersonalTokenService,
+};
+
+/// Creates a personal access token. Only a login session may do so, a
+/// leaked token must not be able to mint successors, and only within the
+/// scope of the session.
+#[post("")]
+pub async fn create_token(
+    user: AuthenticatedUser,
+    request: Json<CreatePersonalToken>,
+    pool: Data<PgPool>,
+    config: Data<AppConfig>,
+) -> Result<HttpResponse, PersonalTokenError> {
+    request.validate()?;
+    if user.session_id.is_none() {
+        return Err(PersonalTokenError::SessionRequired);
+    }
+
+    let token = PersonalTokenService::create(
+        &pool,
+        &config,
+        user.id,
+        user.scope.as_deref(),
+        request.into_inner(),
+    )
+    .await?;
+    Ok(HttpResponse::Created().json(token))
+}
+
+#[get("")]
+pub async fn get_tokens(
+    user: AuthenticatedUser,
+    pool: Data<PgPool>,
+) -> Result<HttpResponse, PersonalTokenError> {
+    let tokens = PersonalTokensRepository::find_all(&pool, user.id).await?;
+    Ok(HttpResponse::Ok().json(tokens))
+}
+
+#[delete("/{token_id}")]
+pub async fn revoke_token(
+    user: AuthenticatedUser,
+    path: Path<PersonalTokenPath>,
+    pool: Data<PgPool>,
+) -> Result<HttpResponse, PersonalTokenError> {
+    if !PersonalTokensRepository::revoke(&pool, user.id, path.token_id).await? {
+        return Err(PersonalTokenError::NotFound);
+    }
+    Ok(HttpResponse::Ok()
+        .json(json!({"message": "Personal access token revoked"})))
+}
+
+pub fn personal_tokens_routes(cfg: &mut ServiceConfig) {
+    let auth = HttpAuthentication::with_fn(
+        crate::middlewares::auth_middleware::auth_middleware_validator,
+    );
+
+    cfg.service(
+        scope("/me/tokens")
+            .wrap(require_scope(ACCOUNT))
+            .wrap(auth)
+            .service(create_token)
+            .service(get_tokens)
+            .service(revoke_token),
+    );
+}
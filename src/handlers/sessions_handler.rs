@@ -0,0 +1,81 @@
+use actix_web::{
+    HttpResponse, Result, delete, get, post,
+    web::{Data, Path, Query, ServiceConfig, scope},
+};
+use actix_web_httpauth::middleware::HttpAuthentication;
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::{
+    errors::sessions_errors::SessionError,
+    extractors::auth_extractor::AuthenticatedUser,
+    middlewares::scope_middleware::require_scope,
+    models::{
+        scope_models::ACCOUNT,
+        sessions_models::{LogoutAllQuery, SessionPath},
+    },
+    repositories::sessions_repository::SessionsRepository,
+    services::denylist_services::TokenDenylist,
+};
+
+#[get("")]
+pub async fn get_sessions(
+    user: AuthenticatedUser,
+    pool: Data<PgPool>,
+) -> Result<HttpResponse, SessionError> {
+    let sessions =
+        SessionsRepository::get_all(&pool, user.id, user.session_id).await?;
+    Ok(HttpResponse::Ok().json(sessions))
+}
+
+#[delete("/{session_id}")]
+pub async fn delete_session(
+    user: AuthenticatedUser,
+    path: Path<SessionPath>,
+    pool: Data<PgPool>,
+) -> Result<HttpResponse, SessionError> {
+    SessionsRepository::delete(&pool, user.id, path.session_id).await?;
+    Ok(HttpResponse::Ok().json(json!({"message": "Session revoked"})))
+}
+
+/// Revokes every session of the user. With `?keep_current=true` the
+/// session of the calling access token stays signed in, otherwise every
+/// access token issued so far is revoked as well.
+#[post("")]
+pub async fn logout_all(
+    user: AuthenticatedUser,
+    query: Query<LogoutAllQuery>,
+    pool: Data<PgPool>,
+    denylist: Data<TokenDenylist>,
+) -> Result<HttpResponse, SessionError> {
+    let keep = if query.keep_current { user.session_id } else { None };
+
+    let revoked = SessionsRepository::delete_all(&pool, user.id, keep).await?;
+    if keep.is_none() {
+        denylist.revoke_user(&pool, user.id).await?;
+    }
+    Ok(HttpResponse::Ok().json(json!({
+        "message": "Sessions revoked",
+        "revoked": revoked
+    })))
+}
+
+pub fn sessions_routes(cfg: &mut ServiceConfig) {
+    let auth = HttpAuthentication::with_fn(
+        crate::middlewares::auth_middleware::auth_middleware_validator,
+    );
+
+    cfg.service(
+        scope("/sessions")
+            .wrap(require_scope(ACCOUNT))
+            .wrap(auth.clone())
+            .service(get_sessions)
+            .service(delete_session),
+    )
+    .service(
+        scope("/logout-all")
+            .wrap(require_scope(ACCOUNT))
+            .wrap(auth)
+            .service(logout_all),
+    );
+}
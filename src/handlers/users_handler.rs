@@ -1,78 +1,199 @@
 use crate::{
+    config::app_config::AppConfig,
     errors::users_errors::UserError,
-    models::users_models::{CreateUser, UpdateUser, UserPath},
+    extractors::auth_extractor::{AuthenticatedUser, OptionalUser},
+    middlewares::{
+        role_middleware::require_role, scope_middleware::require_scope,
+    },
+    models::{
+        public_id_models::PublicRef,
+        role_models::ADMIN_ROLE,
+        scope_models::{USERS_READ, USERS_WRITE},
+        users_models::{CreateUser, UpdateUser, User, UserPath, UserResponse},
+    },
     repositories::users_repository::UserRepository,
+    services::{
+        account_services::AccountService, denylist_services::TokenDenylist,
+        mailer_services::Mailer, password_policy_services::PasswordPolicy,
+        password_services::PasswordService,
+        user_id_cache_services::UserIdCache,
+    },
 };
 use actix_web::{
     HttpResponse, Result, delete, get, post, put,
-    web::{Data, Json, Path, ServiceConfig},
+    web::{self, Data, Json, Path, ServiceConfig, resource, scope},
 };
+use actix_web_httpauth::middleware::HttpAuthentication;
 use sqlx::PgPool;
 use validator::Validate;
 
-#[post("/users")]
+#[post("")]
 pub async fn create_user(
     user_data: Json<CreateUser>,
     pool: Data<PgPool>,
+    config: Data<AppConfig>,
+    policy: Data<PasswordPolicy>,
+    mailer: Data<dyn Mailer>,
 ) -> Result<HttpResponse, UserError> {
     user_data.validate().map_err(UserError::Validation)?;
-    let user = UserRepository::create(&pool, user_data.into_inner()).await?;
-    Ok(HttpResponse::Ok().json(user))
+    policy.check(&user_data.username, &user_data.password).await?;
+
+    let password_hash =
+        PasswordService::hash(&config.password, &user_data.password)?;
+    let user = UserRepository::create(
+        &pool,
+        &user_data.username,
+        &password_hash,
+        user_data.email.as_deref(),
+    )
+    .await?;
+
+    if user.email.is_some() {
+        send_verification(&pool, &config, mailer, &user).await;
+    }
+    Ok(HttpResponse::Ok().json(UserResponse::from(user)))
 }
 
-#[get("/users")]
+/// Admin only, registered in [`users_routes`] behind `require_role` and
+/// `require_scope`.
 pub async fn get_all_users(
     pool: Data<PgPool>,
 ) -> Result<HttpResponse, UserError> {
-    let users = UserRepository::get_all(&pool).await?;
+    let users: Vec<UserResponse> = UserRepository::get_all(&pool)
+        .await?
+        .into_iter()
+        .map(UserResponse::from)
+        .collect();
 
     Ok(HttpResponse::Ok().json(users))
 }
 
-#[get("/users/{user_id}")]
+#[get("/{user_id}")]
 pub async fn get_user(
     path: Path<UserPath>,
+    caller: OptionalUser,
     pool: Data<PgPool>,
 ) -> Result<HttpResponse, UserError> {
-    path.validate().map_err(UserError::Validation)?;
-
-    let user = UserRepository::find_by_id(&pool, path.user_id).await?;
+    let user = UserRepository::find(&pool, path.user_id).await?;
+    // Адрес видят только владелец и администраторы, с токеном users:read
+    let is_owner = caller.0.is_some_and(|caller| {
+        caller.has_scope(USERS_READ)
+            && authorize_owner(&caller, PublicRef::Uuid(user.public_id)).is_ok()
+    });
+    let mut user = UserResponse::from(user);
+    if !is_owner {
+        user.email = None;
+    }
 
     Ok(HttpResponse::Ok().json(user))
 }
-#[put("/users/{user_id}")]
+
+#[put("/{user_id}", wrap = "require_scope(USERS_WRITE)")]
 pub async fn update_user(
+    caller: AuthenticatedUser,
     path: Path<UserPath>,
     user_data: Json<UpdateUser>,
     pool: Data<PgPool>,
+    config: Data<AppConfig>,
+    mailer: Data<dyn Mailer>,
 ) -> Result<HttpResponse, UserError> {
-    path.validate()?;
+    authorize_owner(&caller, path.user_id)?;
     user_data.validate().map_err(UserError::Validation)?;
 
+    let user = UserRepository::find(&pool, path.user_id).await?;
+    let previous_email = user.email;
+
     // Обновление пользователя
-    let updated_user =
-        UserRepository::update(&pool, path.user_id, user_data.into_inner())
-            .await?;
+    let updated_user = UserRepository::update(
+        &pool,
+        user.id,
+        &user_data.username,
+        user_data.email.as_deref(),
+    )
+    .await?;
 
-    Ok(HttpResponse::Ok().json(updated_user))
+    let email_changed = match (&previous_email, &updated_user.email) {
+        (Some(previous), Some(current)) => {
+            !previous.eq_ignore_ascii_case(current)
+        }
+        (None, Some(_)) => true,
+        _ => false,
+    };
+    if email_changed {
+        send_verification(&pool, &config, mailer, &updated_user).await;
+    }
+
+    Ok(HttpResponse::Ok().json(UserResponse::from(updated_user)))
 }
 
-#[delete("/users/{user_id}")]
+#[delete("/{user_id}", wrap = "require_scope(USERS_WRITE)")]
 async fn delete_user(
+    caller: AuthenticatedUser,
     path: Path<UserPath>,
     pool: Data<PgPool>,
+    denylist: Data<TokenDenylist>,
+    user_ids: Data<UserIdCache>,
 ) -> Result<HttpResponse, UserError> {
-    path.validate().map_err(UserError::Validation)?;
+    authorize_owner(&caller, path.user_id)?;
 
-    UserRepository::delete(&pool, path.user_id).await?;
+    let user = UserRepository::find(&pool, path.user_id).await?;
+    UserRepository::delete(&pool, user.id).await?;
+    denylist.revoke_user(&pool, user.id).await?;
+    user_ids.forget(user.public_id);
 
     Ok(HttpResponse::Ok().json(()))
 }
 
+/// Only the owner of an account or an admin may change it or see its
+/// email address.
+fn authorize_owner(
+    caller: &AuthenticatedUser,
+    user: PublicRef,
+) -> Result<(), UserError> {
+    let is_caller = match user {
+        PublicRef::Uuid(public_id) => caller.public_id == public_id,
+        PublicRef::Legacy(id) => caller.id == id,
+    };
+    if is_caller || caller.has_role(ADMIN_ROLE) {
+        Ok(())
+    } else {
+        Err(UserError::Forbidden)
+    }
+}
+
+/// The user is already saved at this point, so a mail failure is only
+/// logged. The link can be requested again later.
+async fn send_verification(
+    pool: &PgPool,
+    config: &AppConfig,
+    mailer: Data<dyn Mailer>,
+    user: &User,
+) {
+    if let Err(e) =
+        AccountService::send_email_verification(pool, config, mailer, user)
+            .await
+    {
+        log::error!("Failed to send verification to user {}: {e}", user.id);
+    }
+}
+
 pub fn users_routes(cfg: &mut ServiceConfig) {
-    cfg.service(create_user)
-        .service(get_user)
-        .service(update_user)
-        .service(get_all_users)
-        .service(delete_user);
+    let auth = HttpAuthentication::with_fn(
+        crate::middlewares::auth_middleware::auth_middleware_validator,
+    );
+
+    cfg.service(
+        scope("/users").service(create_user).service(get_user).service(
+            scope("")
+                .wrap(auth)
+                .service(update_user)
+                .service(delete_user)
+                .service(
+                    resource("")
+                        .wrap(require_scope(USERS_READ))
+                        .wrap(require_role(ADMIN_ROLE))
+                        .route(web::get().to(get_all_users)),
+                ),
+        ),
+    );
 }
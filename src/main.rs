@@ -1,13 +1,24 @@
 use actix_web::{App, HttpServer, middleware::Logger, web::Data};
-use std::env;
 
 use crate::{
-    handlers::ping_pong_handler::get_ping_pong,
+    config::app_config::AppConfig, handlers::ping_pong_handler::get_ping_pong,
     migrations::apply_migrations::apply_migrations,
+    services::{
+        denylist_services::TokenDenylist,
+        key_ring_services::KeyRing,
+        mailer_services::{MailService, Mailer},
+        password_policy_services::PasswordPolicy,
+        password_services::PasswordService,
+        roles_services::RolesService,
+        scheduler_services::Scheduler,
+        user_id_cache_services::UserIdCache,
+    },
 };
 use sqlx::postgres::PgPoolOptions;
 
+mod config;
 mod errors;
+mod extractors;
 mod handlers;
 mod middlewares;
 mod migrations;
@@ -25,32 +36,82 @@ async fn main() -> std::io::Result<()> {
 
     dotenv::dotenv().ok();
 
-    // Create DB pool
-    let database_url =
-        env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let config = AppConfig::load().unwrap_or_else(|e| {
+        log::error!("Invalid configuration: {e}");
+        std::process::exit(1);
+    });
+
+    let key_ring = KeyRing::from_config(&config.jwt).unwrap_or_else(|e| {
+        log::error!("Failed to load JWT keys: {e}");
+        std::process::exit(1);
+    });
+
+    let mailer = MailService::from_config(&config.mail).unwrap_or_else(|e| {
+        log::error!("Failed to set up mail transport: {e}");
+        std::process::exit(1);
+    });
+
+    // Хеш-заглушка создаётся заранее, чтобы первый вход неизвестного
+    // пользователя не отвечал дольше остальных
+    PasswordService::verify_dummy(&config.password, "");
 
-    println!("database_url: {database_url}");
+    let password_policy = PasswordPolicy::from_config(&config.password_policy)
+        .unwrap_or_else(|e| {
+            log::error!("Failed to load password policy: {e}");
+            std::process::exit(1);
+        });
 
+    // Create DB pool
     let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
+        .max_connections(config.database.max_connections)
+        .connect(&config.database.url)
         .await
         .expect("Failed to create pool");
 
     apply_migrations(&pool).await.expect("Failed to apply migrations");
+    RolesService::bootstrap_admins(&pool, &config.admin)
+        .await
+        .expect("Failed to grant admin roles");
+
+    let bind_address = (config.server.host.clone(), config.server.port);
+    let config = Data::new(config);
+    let key_ring = Data::new(key_ring);
+    let mailer: Data<dyn Mailer> = Data::from(mailer);
+    let password_policy = Data::new(password_policy);
+
+    let denylist = Data::new(TokenDenylist::new(&config.jwt));
+    denylist.refresh(&pool).await.expect("Failed to load token denylist");
+    TokenDenylist::spawn_refresh(denylist.clone(), pool.clone());
+    let user_ids = Data::new(UserIdCache::default());
+    Scheduler::spawn(&config, &pool);
 
     HttpServer::new(move || {
         let logger = Logger::default();
         App::new()
             .wrap(logger)
             .app_data(Data::new(pool.clone()))
+            .app_data(config.clone())
+            .app_data(key_ring.clone())
+            .app_data(denylist.clone())
+            .app_data(user_ids.clone())
+            .app_data(mailer.clone())
+            .app_data(password_policy.clone())
             .service(get_ping_pong)
             .configure(handlers::users_handler::users_routes)
             .configure(handlers::cookies_handler::cookie_routes)
             .configure(handlers::posts_handler::posts_routes)
             .configure(handlers::auth_handler::auth_routes)
+            .configure(handlers::jwks_handler::jwks_routes)
+            .configure(handlers::sessions_handler::sessions_routes)
+            .configure(handlers::admin_handler::admin_routes)
+            .configure(handlers::mfa_handler::mfa_routes)
+            .configure(handlers::account_handler::account_routes)
+            .configure(
+                handlers::personal_tokens_handler::personal_tokens_routes,
+            )
+            .configure(handlers::audit_handler::audit_routes)
     })
-    .bind(("127.0.0.1", 3030))?
+    .bind(bind_address)?
     .run()
     .await
 }
@@ -1,23 +1,89 @@
-use crate::services::auth_services::AuthService;
+use crate::{
+    config::app_config::AppConfig,
+    errors::auth_errors::AuthError,
+    services::{
+        auth_services::AuthService, cookie_services::CookieService,
+        denylist_services::TokenDenylist, key_ring_services::KeyRing,
+        user_id_cache_services::UserIdCache,
+    },
+};
 use actix_web::HttpMessage;
-use actix_web::{Error, dev::ServiceRequest};
+use actix_web::{Error, dev::ServiceRequest, web::Data};
 use actix_web_httpauth::extractors::bearer::BearerAuth;
+use sqlx::PgPool;
 
+/// Validates the bearer token (an access or personal access token) or, in
+/// cookie mode, the access token cookie. Register with
+/// `HttpAuthentication::with_fn`, so requests without an `Authorization`
+/// header reach it too.
 pub async fn auth_middleware_validator(
     req: ServiceRequest,
-    credentials: BearerAuth,
+    credentials: Option<BearerAuth>,
 ) -> Result<ServiceRequest, (Error, ServiceRequest)> {
-    let token = credentials.token();
+    let Some(config) = req.app_data::<Data<AppConfig>>() else {
+        log::error!("AppConfig is not registered in app data");
+        return Err((
+            actix_web::error::ErrorInternalServerError("Missing config"),
+            req,
+        ));
+    };
 
-    log::debug!("Validating token: {}", token);
+    let token = match credentials {
+        Some(credentials) => credentials.token().to_string(),
+        None => {
+            match CookieService::access_token(req.request(), &config.cookies) {
+                Ok(Some(token)) => token,
+                Ok(None) => {
+                    return Err((AuthError::Unauthenticated.into(), req));
+                }
+                Err(e) => return Err((e.into(), req)),
+            }
+        }
+    };
+
+    let Some(key_ring) = req.app_data::<Data<KeyRing>>() else {
+        log::error!("KeyRing is not registered in app data");
+        return Err((
+            actix_web::error::ErrorInternalServerError("Missing key ring"),
+            req,
+        ));
+    };
+
+    let Some(denylist) = req.app_data::<Data<TokenDenylist>>() else {
+        log::error!("TokenDenylist is not registered in app data");
+        return Err((
+            actix_web::error::ErrorInternalServerError("Missing denylist"),
+            req,
+        ));
+    };
+
+    let Some(user_ids) = req.app_data::<Data<UserIdCache>>() else {
+        log::error!("UserIdCache is not registered in app data");
+        return Err((
+            actix_web::error::ErrorInternalServerError("Missing user id cache"),
+            req,
+        ));
+    };
+
+    let Some(pool) = req.app_data::<Data<PgPool>>() else {
+        log::error!("PgPool is not registered in app data");
+        return Err((
+            actix_web::error::ErrorInternalServerError("Missing database pool"),
+            req,
+        ));
+    };
 
-    match AuthService::validate_access_token(token) {
+    match AuthService::validate_bearer_token(
+        pool, config, key_ring, denylist, user_ids, &token,
+    )
+    .await
+    {
         Ok(claims) => {
             req.extensions_mut().insert(claims);
             Ok(req)
         }
         Err(e) => {
-            log::warn!("Token validation failed: {}", e);
+            log::warn!("Token validation failed: {e}");
             Err((e.into(), req)) // Теперь возвращаем кортеж (ошибка, запрос)
         }
     }
@@ -1 +1,3 @@
 pub mod auth_middleware;
+pub mod role_middleware;
+pub mod scope_middleware;
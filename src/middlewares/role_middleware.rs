@@ -0,0 +1,83 @@
+use std::{
+    future::{Future, Ready, ready},
+    pin::Pin,
+};
+
+use actix_web::{
+    Error, HttpMessage,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+};
+
+use crate::{errors::auth_errors::AuthError, models::auth_models::Claims};
+
+/// Rejects requests whose access token lacks `role` with 403. It reads the
+/// claims set by the auth middleware, so wrap it inside of it:
+///
+/// ```ignore
+/// scope("/admin").wrap(require_role("admin")).wrap(auth)
+/// ```
+pub fn require_role(role: &'static str) -> RequireRole {
+    RequireRole { role }
+}
+
+pub struct RequireRole {
+    role: &'static str,
+}
+
+pub struct RequireRoleMiddleware<S> {
+    service: S,
+    role: &'static str,
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequireRole
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequireRoleMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireRoleMiddleware { service, role: self.role }))
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for RequireRoleMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let allowed =
+            req.extensions().get::<Claims>().map(|c| c.has_role(self.role));
+
+        match allowed {
+            Some(true) => Box::pin(self.service.call(req)),
+            Some(false) => {
+                let role = self.role;
+                Box::pin(
+                    async move { Err(AuthError::MissingRole(role).into()) },
+                )
+            }
+            None => {
+                log::error!("require_role used without the auth middleware");
+                Box::pin(async {
+                    Err(actix_web::error::ErrorInternalServerError(
+                        "Missing claims",
+                    ))
+                })
+            }
+        }
+    }
+}
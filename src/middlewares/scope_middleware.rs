@@ -0,0 +1,86 @@
+use std::{
+    future::{Future, Ready, ready},
+    pin::Pin,
+};
+
+use actix_web::{
+    Error, HttpMessage,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+};
+
+use crate::{errors::auth_errors::AuthError, models::auth_models::Claims};
+
+/// Rejects requests whose access token is limited to scopes without
+/// `scope` with 403 `insufficient_scope`. Tokens without a `scope` claim
+/// pass. Like [`require_role`](super::role_middleware::require_role) it
+/// needs the claims of the auth middleware, so it is used on routes inside
+/// an authenticated scope:
+///
+/// ```ignore
+/// #[post("", wrap = "require_scope(POSTS_WRITE)")]
+/// ```
+pub fn require_scope(scope: &'static str) -> RequireScope {
+    RequireScope { scope }
+}
+
+pub struct RequireScope {
+    scope: &'static str,
+}
+
+pub struct RequireScopeMiddleware<S> {
+    service: S,
+    scope: &'static str,
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequireScope
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequireScopeMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireScopeMiddleware { service, scope: self.scope }))
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for RequireScopeMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>
+        + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let allowed =
+            req.extensions().get::<Claims>().map(|c| c.has_scope(self.scope));
+
+        match allowed {
+            Some(true) => Box::pin(self.service.call(req)),
+            Some(false) => {
+                let scope = self.scope;
+                Box::pin(async move {
+                    Err(AuthError::InsufficientScope(scope).into())
+                })
+            }
+            None => {
+                log::error!("require_scope used without the auth middleware");
+                Box::pin(async {
+                    Err(actix_web::error::ErrorInternalServerError(
+                        "Missing claims",
+                    ))
+                })
+            }
+        }
+    }
+}
@@ -32,59 +32,57 @@ pub async fn apply_migrations(pool: &PgPool) -> Result<(), Box<dyn Error>> {
     migration_dirs.sort();
 
     for migration_dir in migration_dirs {
-        let version = match migration_dir.split('_').next() {
-            Some(v) => v.parse::<i64>().ok(),
-            None => {
-                println!("Skipping invalid migration dir: {migration_dir}");
-                continue;
-            }
+        let Some(version) = migration_dir
+            .split('_')
+            .next()
+            .and_then(|v| v.parse::<i64>().ok())
+        else {
+            println!("Skipping invalid migration dir: {migration_dir}");
+            continue;
         };
 
-        if let Some(version) = version {
-            if !applied_migrations.contains(&version) {
-                println!("Applying migration: {migration_dir}");
+        if !applied_migrations.contains(&version) {
+            println!("Applying migration: {migration_dir}");
 
-                let up_path =
-                    migrations_dir.join(&migration_dir).join("up.sql");
-                if !up_path.exists() {
-                    println!("Error: up.sql not found in {migration_dir}");
-                    continue;
-                }
+            let up_path = migrations_dir.join(&migration_dir).join("up.sql");
+            if !up_path.exists() {
+                println!("Error: up.sql not found in {migration_dir}");
+                continue;
+            }
 
-                let up_sql = fs::read_to_string(&up_path)?;
-                println!("Executing SQL:\n{up_sql}");
+            let up_sql = fs::read_to_string(&up_path)?;
+            println!("Executing SQL:\n{up_sql}");
 
-                let mut tx = pool.begin().await.map_err(|e| {
-                    println!("Failed to begin transaction: {e}");
-                    e
-                })?;
+            let mut tx = pool.begin().await.map_err(|e| {
+                println!("Failed to begin transaction: {e}");
+                e
+            })?;
 
-                match sqlx::query(&up_sql).execute(&mut *tx).await {
-                    Ok(_) => {
-                        match sqlx::query("INSERT INTO schema_migrations (version) VALUES ($1)")
-                            .bind(version)
-                            .execute(&mut *tx)
-                            .await
-                        {
-                            Ok(_) => {
-                                tx.commit().await.map_err(|e| {
-                                    println!("Failed to commit transaction: {e}");
-                                    e
-                                })?;
-                                println!("Migration {version} applied successfully");
-                            }
-                            Err(e) => {
-                                println!("Failed to record migration: {e}");
-                                tx.rollback().await.ok();
-                                return Err(e.into());
-                            }
+            match sqlx::raw_sql(&up_sql).execute(&mut *tx).await {
+                Ok(_) => {
+                    match sqlx::query("INSERT INTO schema_migrations (version) VALUES ($1)")
+                        .bind(version)
+                        .execute(&mut *tx)
+                        .await
+                    {
+                        Ok(_) => {
+                            tx.commit().await.map_err(|e| {
+                                println!("Failed to commit transaction: {e}");
+                                e
+                            })?;
+                            println!("Migration {version} applied successfully");
+                        }
+                        Err(e) => {
+                            println!("Failed to record migration: {e}");
+                            tx.rollback().await.ok();
+                            return Err(e.into());
                         }
                     }
-                    Err(e) => {
-                        println!("Failed to execute migration: {e}");
-                        tx.rollback().await.ok();
-                        return Err(e.into());
-                    }
+                }
+                Err(e) => {
+                    println!("Failed to execute migration: {e}");
+                    tx.rollback().await.ok();
+                    return Err(e.into());
                 }
             }
         }
@@ -0,0 +1,71 @@
+use serde::Deserialize;
+use sqlx::FromRow;
+use validator::Validate;
+
+/// What a token sent by mail can be used for.
+#[derive(Debug, Clone, Copy)]
+pub enum TokenPurpose {
+    PasswordReset,
+    EmailVerification,
+}
+
+impl TokenPurpose {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TokenPurpose::PasswordReset => "password_reset",
+            TokenPurpose::EmailVerification => "email_verification",
+        }
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct UserTokenRecord {
+    pub user_id: i32,
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PasswordResetRequest {
+    #[validate(email(message = "Invalid email address"))]
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PasswordResetConfirm {
+    #[validate(length(
+        min = 36,
+        max = 36,
+        message = "Token must be 36 characters long"
+    ))]
+    pub token: String,
+
+    #[validate(length(
+        min = 8,
+        max = 64,
+        message = "Password must be between 8 and 64 characters"
+    ))]
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PasswordChangeRequest {
+    #[validate(length(min = 1, message = "Current password is required"))]
+    pub current_password: String,
+
+    #[validate(length(
+        min = 8,
+        max = 64,
+        message = "Password must be between 8 and 64 characters"
+    ))]
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct EmailVerificationConfirm {
+    #[validate(length(
+        min = 36,
+        max = 36,
+        message = "Token must be 36 characters long"
+    ))]
+    pub token: String,
+}
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// Clears the failed login counters of a username, a client IP or both.
+#[derive(Debug, Deserialize, Validate)]
+pub struct UnlockLoginRequest {
+    #[validate(length(min = 1, max = 25))]
+    pub username: Option<String>,
+
+    #[validate(ip)]
+    pub ip_address: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnlockLoginResponse {
+    pub unlocked: u64,
+}
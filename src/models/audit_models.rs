@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::public_id_models::PublicRef;
+
+#[derive(Debug, Clone, Copy)]
+pub enum AuthEventType {
+    Login,
+    LoginMfa,
+    Refresh,
+    Logout,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum AuthOutcome {
+    Success,
+    Failure,
+}
+
+impl AuthEventType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuthEventType::Login => "login",
+            AuthEventType::LoginMfa => "login_mfa",
+            AuthEventType::Refresh => "refresh",
+            AuthEventType::Logout => "logout",
+        }
+    }
+}
+
+impl AuthOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuthOutcome::Success => "success",
+            AuthOutcome::Failure => "failure",
+        }
+    }
+}
+
+/// An event about to be recorded. The auth flows fill in the user as soon
+/// as they know it, the outcome is taken from their result.
+#[derive(Debug)]
+pub struct AuthEvent {
+    pub event_type: AuthEventType,
+    pub user_id: Option<i32>,
+    /// Name a login was attempted for. Failed logins of an existing user
+    /// are tied to that user by it.
+    pub username: Option<String>,
+    pub outcome: AuthOutcome,
+    pub reason: Option<&'static str>,
+}
+
+impl AuthEvent {
+    pub fn new(event_type: AuthEventType) -> Self {
+        AuthEvent {
+            event_type,
+            user_id: None,
+            username: None,
+            outcome: AuthOutcome::Success,
+            reason: None,
+        }
+    }
+}
+
+#[derive(Debug, FromRow, Serialize)]
+pub struct AuthEventRecord {
+    pub id: i64,
+    /// Public id of the user, `None` for unknown usernames and deleted
+    /// users.
+    pub user_id: Option<Uuid>,
+    pub username: Option<String>,
+    pub event_type: String,
+    pub outcome: String,
+    pub reason: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+fn default_limit() -> i64 {
+    50
+}
+
+/// Page of the caller's own events, newest first.
+#[derive(Debug, Deserialize, Validate)]
+pub struct SecurityEventsQuery {
+    #[serde(default = "default_limit")]
+    #[validate(range(min = 1, max = 500))]
+    pub limit: i64,
+
+    /// Only events older than this id, for the next page.
+    pub before_id: Option<i64>,
+}
+
+/// Filters of the admin event search. All of them are optional.
+#[derive(Debug, Deserialize, Validate)]
+pub struct AuthEventsQuery {
+    pub user_id: Option<PublicRef>,
+
+    #[validate(length(min = 1, max = 255))]
+    pub username: Option<String>,
+
+    #[validate(length(min = 1, max = 32))]
+    pub event_type: Option<String>,
+
+    #[validate(length(min = 1, max = 16))]
+    pub outcome: Option<String>,
+
+    #[validate(ip)]
+    pub ip_address: Option<String>,
+
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub since: Option<OffsetDateTime>,
+
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub until: Option<OffsetDateTime>,
+
+    #[serde(default = "default_limit")]
+    #[validate(range(min = 1, max = 500))]
+    pub limit: i64,
+
+    pub before_id: Option<i64>,
+}
@@ -1,13 +1,56 @@
+use std::net::IpAddr;
+
+use actix_web::{
+    HttpRequest,
+    http::header::{USER_AGENT, X_FORWARDED_FOR},
+};
 use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
 use time::{Duration, OffsetDateTime};
-use uuid::Uuid;
+use uuid::{Builder, Uuid};
 use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::{
+    config::app_config::{JwtConfig, ServerConfig},
+    models::{
+        mfa_models::MfaChallenge, personal_tokens_models::PersonalTokenRecord,
+    },
+    services::{
+        scope_services::ScopeService, token_hash_services::TokenHashService,
+    },
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
-    pub sub: i32, // user id
-    pub exp: i32,
-    pub iat: i32,
+    /// Public id of the user.
+    pub sub: Uuid,
+    pub exp: i64,
+    pub iat: i64,
+    /// Microseconds of `iat` within its second, so a token issued right
+    /// after a revocation in the same second is not taken for an older one.
+    #[serde(default)]
+    pub iat_us: u32,
+    pub nbf: i64,
+    pub iss: String,
+    /// Service the token was issued for, one of `jwt.audiences`.
+    pub aud: String,
+    /// Unique token id, used to revoke it before `exp`.
+    pub jti: Uuid,
+    /// Session (refresh token family) the token was issued for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<Uuid>,
+    /// Roles of the user when the token was issued. A revoked role stays
+    /// in tokens issued before, until they expire or are revoked.
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// Space-separated scopes the token is limited to. `None` grants
+    /// everything the roles allow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// Internal id of the user, resolved from `sub` once the token is
+    /// validated. Never part of the token.
+    #[serde(skip)]
+    pub user_id: i32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -16,32 +59,232 @@ pub struct TokenPair {
     pub refresh_token: String,
 }
 
+/// Response body in cookie mode. The refresh token is only sent as a
+/// cookie; the access token is left out too when it has a cookie.
+#[derive(Debug, Serialize)]
+pub struct CookieTokens {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_token: Option<String>,
+    pub csrf_token: String,
+}
+
+/// Result of a login: tokens, or a challenge when 2FA is enabled.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Tokens(TokenPair),
+    MfaRequired(MfaChallenge),
+}
+
+/// A refresh token and its family. Every token issued by rotation from the
+/// same login shares the `family_id` of the token it replaced. Only
+/// `token_hash` is persisted, `token` is handed to the client once.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RefreshToken {
     pub token: String,
+    pub token_hash: String,
+    pub user_id: i32,
+    pub family_id: Uuid,
+    pub scope: Option<String>,
+    pub audience: Option<String>,
+    pub expires_at: OffsetDateTime,
+}
+
+/// What a login granted: the user, the refresh token family, the scope
+/// the session was limited to and its audience (`None` for the default
+/// one). Every token of the family keeps them.
+#[derive(Debug)]
+pub struct TokenGrant {
+    pub user_id: i32,
+    pub family_id: Uuid,
+    pub scope: Option<String>,
+    pub audience: Option<String>,
+}
+
+/// Client details stored with a refresh token so users can recognise
+/// their sessions.
+#[derive(Debug, Default)]
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct RefreshTokenRecord {
     pub user_id: i32,
+    pub family_id: Uuid,
+    pub scope: Option<String>,
+    pub audience: Option<String>,
     pub expires_at: OffsetDateTime,
+    pub consumed_at: Option<OffsetDateTime>,
+    /// `jti` of the access token issued when the token was rotated.
+    pub successor_jti: Option<Uuid>,
 }
 
 impl Claims {
-    pub fn new(user_id: i32) -> Self {
-        let iat = OffsetDateTime::now_utc();
-        let exp = iat + Duration::minutes(3); // Access token expires in 3 minutes
+    /// Claims of an access token of the grant's session. `jti` and
+    /// `issued_at` are given, so the same token can be signed again.
+    pub fn new(
+        config: &JwtConfig,
+        grant: &TokenGrant,
+        public_id: Uuid,
+        roles: Vec<String>,
+        scope: Option<String>,
+        jti: Uuid,
+        issued_at: OffsetDateTime,
+    ) -> Self {
+        let iat = issued_at.unix_timestamp();
+        let exp = issued_at + config.access_token_ttl();
 
         Claims {
-            sub: user_id,
-            exp: exp.unix_timestamp() as i32,
-            iat: iat.unix_timestamp() as i32,
+            sub: public_id,
+            jti,
+            sid: Some(grant.family_id),
+            roles,
+            scope,
+            exp: exp.unix_timestamp(),
+            iat,
+            iat_us: issued_at.microsecond(),
+            nbf: iat,
+            iss: config.issuer.clone(),
+            aud: grant
+                .audience
+                .as_deref()
+                .unwrap_or_else(|| config.default_audience())
+                .to_string(),
+            user_id: grant.user_id,
         }
     }
+
+    /// Claims equivalent to a personal access token. `jti` is the token id
+    /// and there is no session; a token without expiry gets the largest
+    /// `exp`. Its audience is the default one.
+    pub fn for_personal_token(
+        config: &JwtConfig,
+        record: &PersonalTokenRecord,
+        roles: Vec<String>,
+    ) -> Self {
+        Claims {
+            sub: record.public_id,
+            jti: record.id,
+            sid: None,
+            roles,
+            scope: Some(record.scopes.join(" ")),
+            exp: record
+                .expires_at
+                .map_or(i64::MAX, OffsetDateTime::unix_timestamp),
+            iat: record.created_at.unix_timestamp(),
+            iat_us: record.created_at.microsecond(),
+            nbf: record.created_at.unix_timestamp(),
+            iss: config.issuer.clone(),
+            aud: config.default_audience().to_string(),
+            user_id: record.user_id,
+        }
+    }
+
+    /// `iat` in microseconds since the Unix epoch.
+    pub fn issued_at_us(&self) -> i64 {
+        self.iat
+            .saturating_mul(1_000_000)
+            .saturating_add(i64::from(self.iat_us))
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scope
+            .as_deref()
+            .is_none_or(|granted| ScopeService::contains(granted, scope))
+    }
+}
+
+impl ClientInfo {
+    pub fn from_request(req: &HttpRequest, server: &ServerConfig) -> Self {
+        ClientInfo {
+            user_agent: req
+                .headers()
+                .get(USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string),
+            ip_address: Self::client_ip(req, &server.trusted_proxies)
+                .map(|ip| ip.to_string()),
+        }
+    }
+
+    /// The peer address, or behind trusted proxies the address the first
+    /// of them was connected from. Entries left of it may be forged by the
+    /// client and are ignored.
+    fn client_ip(req: &HttpRequest, trusted: &[IpAddr]) -> Option<IpAddr> {
+        let mut client = req.peer_addr()?.ip();
+        if !trusted.contains(&client) {
+            return Some(client);
+        }
+
+        // Каждый прокси дописывает адрес своего клиента в конец заголовка
+        let forwarded: Vec<&str> = req
+            .headers()
+            .get_all(X_FORWARDED_FOR)
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect();
+        for hop in forwarded.into_iter().rev() {
+            let Ok(ip) = hop.parse::<IpAddr>() else {
+                break;
+            };
+            client = ip;
+            if !trusted.contains(&ip) {
+                break;
+            }
+        }
+
+        Some(client)
+    }
 }
 
 impl RefreshToken {
-    pub fn new(user_id: i32) -> Self {
-        let token = Uuid::new_v4().to_string();
-        let expires_at = OffsetDateTime::now_utc() + Duration::days(30); // Refresh token expires in 30 days
+    pub fn new(grant: &TokenGrant, ttl: Duration, hmac_key: &str) -> Self {
+        Self::with_token(grant, ttl, hmac_key, Uuid::new_v4().to_string())
+    }
+
+    /// The token `previous` is rotated to. It is derived from `previous`
+    /// under the HMAC key, so it can be handed out again during the grace
+    /// period without being stored.
+    pub fn successor(
+        grant: &TokenGrant,
+        ttl: Duration,
+        hmac_key: &str,
+        previous: &str,
+    ) -> Self {
+        let hash =
+            TokenHashService::hash(hmac_key, &format!("successor:{previous}"));
+        let mut bytes = [0u8; 16];
+        hex::decode_to_slice(&hash[..32], &mut bytes)
+            .expect("HMAC hashes are hex encoded");
+        let token = Builder::from_random_bytes(bytes).into_uuid().to_string();
+        Self::with_token(grant, ttl, hmac_key, token)
+    }
+
+    fn with_token(
+        grant: &TokenGrant,
+        ttl: Duration,
+        hmac_key: &str,
+        token: String,
+    ) -> Self {
+        let token_hash = TokenHashService::hash(hmac_key, &token);
+        let expires_at = OffsetDateTime::now_utc() + ttl;
 
-        RefreshToken { token, user_id, expires_at }
+        RefreshToken {
+            token,
+            token_hash,
+            user_id: grant.user_id,
+            family_id: grant.family_id,
+            scope: grant.scope.clone(),
+            audience: grant.audience.clone(),
+            expires_at,
+        }
     }
 }
 
@@ -55,6 +298,14 @@ pub struct LoginRequest {
         message = "Password must be between 8 and 64 characters"
     ))]
     pub password: String,
+
+    /// Space-separated scopes to limit the session to, e.g. `posts:read`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// Service the session's access tokens are for, one of
+    /// `jwt.audiences`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audience: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
@@ -65,4 +316,84 @@ pub struct RefreshRequest {
         message = "Refresh token must be 36 characters long"
     ))]
     pub refresh_token: String,
+
+    /// Narrows the scope of the new access token. The refresh token keeps
+    /// the scope of its session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    fn client_ip(request: TestRequest, trusted: &[&str]) -> Option<String> {
+        let server = ServerConfig {
+            trusted_proxies: trusted
+                .iter()
+                .map(|ip| ip.parse().unwrap())
+                .collect(),
+            ..ServerConfig::default()
+        };
+        ClientInfo::from_request(&request.to_http_request(), &server).ip_address
+    }
+
+    fn from_peer(peer: &str) -> TestRequest {
+        TestRequest::default()
+            .peer_addr(format!("{peer}:4000").parse().unwrap())
+    }
+
+    #[test]
+    fn untrusted_peer_is_the_client() {
+        let request = from_peer("203.0.113.7")
+            .insert_header((X_FORWARDED_FOR, "198.51.100.1"));
+
+        assert_eq!(client_ip(request, &[]).as_deref(), Some("203.0.113.7"));
+    }
+
+    #[test]
+    fn forwarded_for_is_ignored_from_untrusted_peers() {
+        let request = from_peer("203.0.113.7")
+            .insert_header((X_FORWARDED_FOR, "198.51.100.1"));
+
+        assert_eq!(
+            client_ip(request, &["10.0.0.1"]).as_deref(),
+            Some("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn trusted_proxies_are_skipped_from_the_right() {
+        // Клиент подделал первый адрес, второй дописал внешний прокси
+        let request = from_peer("10.0.0.1").insert_header((
+            X_FORWARDED_FOR,
+            "192.0.2.99, 198.51.100.1, 10.0.0.2",
+        ));
+
+        assert_eq!(
+            client_ip(request, &["10.0.0.1", "10.0.0.2"]).as_deref(),
+            Some("198.51.100.1")
+        );
+    }
+
+    #[test]
+    fn malformed_hops_stop_at_the_last_trusted_address() {
+        let request = from_peer("10.0.0.1")
+            .insert_header((X_FORWARDED_FOR, "198.51.100.1, unknown"));
+
+        assert_eq!(
+            client_ip(request, &["10.0.0.1"]).as_deref(),
+            Some("10.0.0.1")
+        );
+    }
+
+    #[test]
+    fn missing_header_keeps_the_proxy_address() {
+        assert_eq!(
+            client_ip(from_peer("10.0.0.1"), &["10.0.0.1"]).as_deref(),
+            Some("10.0.0.1")
+        );
+    }
 }
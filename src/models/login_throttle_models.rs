@@ -0,0 +1,15 @@
+/// What a failed login counter is kept for.
+#[derive(Debug, Clone, Copy)]
+pub enum ThrottleScope {
+    Username,
+    Ip,
+}
+
+impl ThrottleScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ThrottleScope::Username => "username",
+            ThrottleScope::Ip => "ip",
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use time::OffsetDateTime;
+use validator::Validate;
+
+/// Value of `token_type` in the response of a login that still needs a
+/// second factor.
+pub const MFA_PENDING: &str = "mfa_pending";
+
+#[derive(Debug, FromRow)]
+pub struct TotpState {
+    pub username: String,
+    pub totp_secret: Option<String>,
+    pub totp_enabled: bool,
+}
+
+#[derive(Debug, FromRow)]
+pub struct MfaChallengeRecord {
+    pub user_id: i32,
+    /// Failed codes count against the login throttle of this username.
+    pub username: String,
+    /// Scope requested at `/login`, applied once the challenge is passed.
+    pub scope: Option<String>,
+    /// Audience requested at `/login`.
+    pub audience: Option<String>,
+    pub attempts: i32,
+    pub expires_at: OffsetDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TotpEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
+
+/// Returned by `/login` instead of a token pair when 2FA is enabled.
+/// `mfa_token` is exchanged for tokens at `/login/mfa`.
+#[derive(Debug, Serialize)]
+pub struct MfaChallenge {
+    pub token_type: &'static str,
+    pub mfa_token: String,
+    pub expires_in: i64,
+}
+
+/// A TOTP code or, instead, one of the recovery codes.
+#[derive(Debug, Deserialize, Validate)]
+pub struct MfaCodeRequest {
+    #[validate(length(
+        min = 6,
+        max = 16,
+        message = "Code must be between 6 and 16 characters"
+    ))]
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MfaLoginRequest {
+    #[validate(length(
+        min = 36,
+        max = 36,
+        message = "MFA token must be 36 characters long"
+    ))]
+    pub mfa_token: String,
+
+    #[validate(length(
+        min = 6,
+        max = 16,
+        message = "Code must be between 6 and 16 characters"
+    ))]
+    pub code: String,
+}
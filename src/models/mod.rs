@@ -1,5 +1,16 @@
+pub mod account_models;
+pub mod admin_models;
+pub mod audit_models;
 pub mod auth_models;
 pub mod cookies_models;
+pub mod login_throttle_models;
+pub mod mfa_models;
+pub mod password_policy_models;
+pub mod personal_tokens_models;
 pub mod ping_pong_models;
 pub mod posts_models;
+pub mod public_id_models;
+pub mod role_models;
+pub mod scope_models;
+pub mod sessions_models;
 pub mod users_models;
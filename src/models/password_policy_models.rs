@@ -0,0 +1,23 @@
+use serde::Serialize;
+
+/// A policy rule a password failed, returned in the error details.
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyViolation {
+    pub rule: &'static str,
+    pub message: &'static str,
+}
+
+pub const MIN_ENTROPY: PolicyViolation = PolicyViolation {
+    rule: "min_entropy",
+    message: "Password is too easy to guess, make it longer or mix letters, digits and symbols",
+};
+
+pub const CONTAINS_USERNAME: PolicyViolation = PolicyViolation {
+    rule: "contains_username",
+    message: "Password must not contain the username",
+};
+
+pub const BREACHED: PolicyViolation = PolicyViolation {
+    rule: "breached",
+    message: "Password appears in a list of breached or common passwords",
+};
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Every personal access token starts with this, so the middleware can
+/// tell it from a JWT and secret scanners can recognise leaked tokens.
+pub const PERSONAL_TOKEN_PREFIX: &str = "pat_";
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreatePersonalToken {
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Name must be between 1 and 100 chars"
+    ))]
+    pub name: String,
+
+    #[validate(length(min = 1, message = "At least one scope is required"))]
+    pub scopes: Vec<String>,
+
+    /// Days until the token expires. Without it the token is valid until
+    /// revoked.
+    #[validate(range(
+        min = 1,
+        max = 3650,
+        message = "Expiry must be between 1 and 3650 days"
+    ))]
+    pub expires_in_days: Option<i64>,
+}
+
+/// A token as listed to its owner. The secret itself is never returned
+/// again after creation.
+#[derive(Debug, FromRow, Serialize)]
+pub struct PersonalToken {
+    pub id: Uuid,
+    pub name: String,
+    pub token_prefix: String,
+    pub scopes: Vec<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub last_used_at: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub expires_at: Option<OffsetDateTime>,
+}
+
+/// Response to a token creation, the only time `token` is shown.
+#[derive(Debug, Serialize)]
+pub struct NewPersonalToken {
+    #[serde(flatten)]
+    pub details: PersonalToken,
+    pub token: String,
+}
+
+/// A valid token, found by its hash.
+#[derive(Debug, FromRow)]
+pub struct PersonalTokenRecord {
+    pub id: Uuid,
+    pub user_id: i32,
+    /// Public id of the owner.
+    pub public_id: Uuid,
+    pub scopes: Vec<String>,
+    pub created_at: OffsetDateTime,
+    pub expires_at: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PersonalTokenPath {
+    pub token_id: Uuid,
+}
@@ -2,13 +2,30 @@ use derive_more::Display;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use time::OffsetDateTime;
+use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, FromRow, Serialize, Deserialize)]
+use crate::models::public_id_models::PublicRef;
+
+/// A row of `posts` with the public id of its author. Handlers return
+/// [`PostResponse`].
+#[derive(Debug, FromRow)]
 pub struct Post {
     pub id: i32,
+    pub public_id: Uuid,
     pub message: String,
     pub user_id: i32,
+    pub author_id: Uuid,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+}
+
+/// What the API exposes about a post. Both ids are public ids.
+#[derive(Debug, Serialize)]
+pub struct PostResponse {
+    pub id: Uuid,
+    pub message: String,
+    pub user_id: Uuid,
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
 }
@@ -26,14 +43,7 @@ pub struct CreatePost {
 #[derive(Debug, Deserialize, Validate, Display)]
 #[display("GetAllPosts: user_id={user_id}")]
 pub struct GetAllPosts {
-    pub user_id: i32,
-}
-
-#[derive(Debug, Deserialize, Validate, Display)]
-#[display("GetPost: id={id}, user_id={user_id}")]
-pub struct GetPost {
-    pub user_id: i32,
-    pub id: i32,
+    pub user_id: PublicRef,
 }
 
 #[derive(Debug, Deserialize, Validate, Display)]
@@ -42,15 +52,21 @@ pub struct UpdatePost {
     pub message: String,
 }
 
-#[derive(Debug, Deserialize, Validate, Display)]
-#[display("DeletePost: id={id}")]
-pub struct DeletePost {
-    pub id: i32,
+#[derive(Debug, Deserialize)]
+pub struct PostsPath {
+    #[serde(deserialize_with = "PublicRef::from_path")]
+    pub post_id: PublicRef,
 }
 
-#[derive(Debug, Deserialize, Validate)]
-pub struct PostsPath {
-    #[validate(range(min = 1, message = "Post ID must be positive"))]
-    pub post_id: i32,
+impl From<Post> for PostResponse {
+    fn from(post: Post) -> Self {
+        PostResponse {
+            id: post.public_id,
+            message: post.message,
+            user_id: post.author_id,
+            created_at: post.created_at,
+            updated_at: post.updated_at,
+        }
+    }
 }
 
@@ -0,0 +1,159 @@
+use std::fmt;
+
+use serde::{
+    Deserialize, Deserializer,
+    de::{self, Visitor},
+};
+use uuid::Uuid;
+
+/// A user or post as named in a route or filter: its public UUID, or its
+/// integer id. Integer ids are deprecated and only accepted until clients
+/// have moved to the UUIDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublicRef {
+    Uuid(Uuid),
+    Legacy(i32),
+}
+
+impl PublicRef {
+    /// The two lookup keys, exactly one of them set, for queries of the
+    /// form `WHERE public_id = $1 OR id = $2`.
+    pub fn keys(self) -> (Option<Uuid>, Option<i32>) {
+        match self {
+            PublicRef::Uuid(public_id) => (Some(public_id), None),
+            PublicRef::Legacy(id) => {
+                log::warn!("Deprecated integer id {id} used in a request");
+                (None, Some(id))
+            }
+        }
+    }
+
+    /// For path segments, whose deserializer only hands out strings. Use
+    /// with `#[serde(deserialize_with = "PublicRef::from_path")]`.
+    pub fn from_path<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(PublicRefVisitor)
+    }
+}
+
+impl fmt::Display for PublicRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PublicRef::Uuid(public_id) => public_id.fmt(f),
+            PublicRef::Legacy(id) => id.fmt(f),
+        }
+    }
+}
+
+struct PublicRefVisitor;
+
+impl Visitor<'_> for PublicRefVisitor {
+    type Value = PublicRef;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a UUID or a positive integer id")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<PublicRef, E> {
+        if let Ok(public_id) = Uuid::parse_str(value) {
+            return Ok(PublicRef::Uuid(public_id));
+        }
+        value
+            .parse::<i64>()
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+            .and_then(|id| self.visit_i64(id))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<PublicRef, E> {
+        i32::try_from(value)
+            .ok()
+            .filter(|&id| id > 0)
+            .map(PublicRef::Legacy)
+            .ok_or_else(|| {
+                E::invalid_value(de::Unexpected::Signed(value), &self)
+            })
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<PublicRef, E> {
+        i64::try_from(value)
+            .map_err(|_| {
+                E::invalid_value(de::Unexpected::Unsigned(value), &self)
+            })
+            .and_then(|id| self.visit_i64(id))
+    }
+}
+
+// JSON может прислать и число, и строку
+impl<'de> Deserialize<'de> for PublicRef {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(PublicRefVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::value::{Error as ValueError, StrDeserializer};
+
+    use super::*;
+
+    const PUBLIC_ID: &str = "7f397ef4-2c95-41dd-a710-dc2f622f7fbe";
+
+    fn from_json(json: &str) -> Result<PublicRef, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    fn from_path(segment: &str) -> Result<PublicRef, ValueError> {
+        PublicRef::from_path(StrDeserializer::<ValueError>::new(segment))
+    }
+
+    #[test]
+    fn deserializes_uuid_strings() {
+        let expected = PublicRef::Uuid(Uuid::parse_str(PUBLIC_ID).unwrap());
+
+        assert_eq!(from_json(&format!("\"{PUBLIC_ID}\"")).unwrap(), expected);
+        assert_eq!(from_path(PUBLIC_ID).unwrap(), expected);
+    }
+
+    #[test]
+    fn deserializes_legacy_integer_ids() {
+        assert_eq!(from_json("42").unwrap(), PublicRef::Legacy(42));
+        assert_eq!(from_json("\"42\"").unwrap(), PublicRef::Legacy(42));
+        assert_eq!(from_path("42").unwrap(), PublicRef::Legacy(42));
+    }
+
+    #[test]
+    fn rejects_ids_out_of_range() {
+        assert!(from_json("0").is_err());
+        assert!(from_json("-1").is_err());
+        assert!(from_json("2147483648").is_err());
+        assert!(from_json("18446744073709551615").is_err());
+        assert!(from_path("-7").is_err());
+    }
+
+    #[test]
+    fn rejects_other_values() {
+        assert!(from_json("\"not-an-id\"").is_err());
+        assert!(from_json("1.5").is_err());
+        assert!(from_json("null").is_err());
+        assert!(from_path("").is_err());
+    }
+
+    #[test]
+    fn keys_set_exactly_one_lookup() {
+        let public_id = Uuid::parse_str(PUBLIC_ID).unwrap();
+
+        assert_eq!(PublicRef::Uuid(public_id).keys(), (Some(public_id), None));
+        assert_eq!(PublicRef::Legacy(7).keys(), (None, Some(7)));
+    }
+
+    #[test]
+    fn displays_like_the_path_segment() {
+        let public_id = Uuid::parse_str(PUBLIC_ID).unwrap();
+
+        assert_eq!(PublicRef::Uuid(public_id).to_string(), PUBLIC_ID);
+        assert_eq!(PublicRef::Legacy(7).to_string(), "7");
+    }
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::public_id_models::PublicRef;
+
+/// Role every new user is given.
+pub const DEFAULT_ROLE: &str = "user";
+pub const ADMIN_ROLE: &str = "admin";
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UserRolePath {
+    #[serde(deserialize_with = "PublicRef::from_path")]
+    pub user_id: PublicRef,
+
+    #[validate(length(min = 1, max = 50))]
+    pub role: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserRoles {
+    pub user_id: Uuid,
+    pub roles: Vec<String>,
+    /// Permissions granted by any of the roles.
+    pub permissions: Vec<String>,
+}
+
+/// A role with the permissions listed for it in `role_permissions`.
+#[derive(Debug, Serialize)]
+pub struct RolePermissions {
+    pub name: String,
+    pub description: String,
+    pub permissions: Vec<String>,
+}
@@ -0,0 +1,17 @@
+/// Scopes a token can be limited to. A token without a `scope` claim is
+/// not limited.
+pub const POSTS_READ: &str = "posts:read";
+pub const POSTS_WRITE: &str = "posts:write";
+pub const USERS_READ: &str = "users:read";
+pub const USERS_WRITE: &str = "users:write";
+/// Everything under `/admin`, on top of the `admin` role.
+pub const ADMIN: &str = "admin";
+/// Managing the account itself: sessions, personal access tokens and 2FA.
+pub const ACCOUNT: &str = "account";
+
+pub const SCOPES: [&str; 6] =
+    [POSTS_READ, POSTS_WRITE, USERS_READ, USERS_WRITE, ADMIN, ACCOUNT];
+
+pub fn is_known(scope: &str) -> bool {
+    SCOPES.contains(&scope)
+}
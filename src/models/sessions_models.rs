@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// An active login of the user, backed by the live refresh token of a
+/// token family.
+#[derive(Debug, FromRow, Serialize)]
+pub struct Session {
+    pub id: Uuid,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub last_used_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: OffsetDateTime,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub current: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SessionPath {
+    pub session_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogoutAllQuery {
+    #[serde(default)]
+    pub keep_current: bool,
+}
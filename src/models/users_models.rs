@@ -1,17 +1,35 @@
+use std::fmt;
+
 use derive_more::Display;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, FromRow, Serialize, Deserialize)]
+use crate::models::public_id_models::PublicRef;
+
+/// A row of `users`. Holds the password hash, so it is never serialized;
+/// handlers return [`UserResponse`].
+#[derive(FromRow)]
 pub struct User {
     pub id: i32,
+    pub public_id: Uuid,
     pub username: String,
     pub password: String,
+    pub email: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Validate, Display)]
-#[display("CreateUser: username={username}, password={password}")]
+/// What the API exposes about a user.
+#[derive(Debug, Serialize)]
+pub struct UserResponse {
+    /// The public id; integer ids are never exposed.
+    pub id: Uuid,
+    pub username: String,
+    pub email: Option<String>,
+}
+
+#[derive(Deserialize, Validate, Display)]
+#[display("CreateUser: username={username}, password=***")]
 pub struct CreateUser {
     #[validate(length(
         min = 3,
@@ -22,10 +40,15 @@ pub struct CreateUser {
 
     #[validate(length(min = 8))]
     pub password: String,
+
+    #[validate(email(message = "Invalid email address"))]
+    pub email: Option<String>,
 }
 
+/// The password is changed with `POST /me/password`, which needs the
+/// current one.
 #[derive(Debug, Deserialize, Validate, Display)]
-#[display("UpdateUser: username={username}, password={password}")]
+#[display("UpdateUser: username={username}")]
 pub struct UpdateUser {
     #[validate(length(
         min = 3,
@@ -34,12 +57,46 @@ pub struct UpdateUser {
     ))]
     pub username: String,
 
-    #[validate(length(min = 8))]
-    pub password: String,
+    #[validate(email(message = "Invalid email address"))]
+    pub email: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize)]
 pub struct UserPath {
-    #[validate(range(min = 1, message = "User ID must be positive"))]
-    pub user_id: i32,
+    #[serde(deserialize_with = "PublicRef::from_path")]
+    pub user_id: PublicRef,
+}
+
+impl From<User> for UserResponse {
+    fn from(user: User) -> Self {
+        UserResponse {
+            id: user.public_id,
+            username: user.username,
+            email: user.email,
+        }
+    }
+}
+
+// Debug пишется вручную, чтобы пароль и хеш не попали в логи
+
+impl fmt::Debug for User {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("User")
+            .field("id", &self.id)
+            .field("public_id", &self.public_id)
+            .field("username", &self.username)
+            .field("password", &"***")
+            .field("email", &self.email)
+            .finish()
+    }
+}
+
+impl fmt::Debug for CreateUser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreateUser")
+            .field("username", &self.username)
+            .field("password", &"***")
+            .field("email", &self.email)
+            .finish()
+    }
 }
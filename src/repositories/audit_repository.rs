@@ -0,0 +1,139 @@
+use sqlx::{Error as SqlxError, PgPool};
+
+use crate::models::{
+    audit_models::{AuthEvent, AuthEventRecord, AuthEventsQuery},
+    auth_models::ClientInfo,
+    public_id_models::PublicRef,
+};
+
+/// The `auth_events` audit trail.
+pub struct AuditRepository;
+
+impl AuditRepository {
+    /// Stores the event. A failed login for an existing username is tied
+    /// to that user.
+    pub async fn insert(
+        pool: &PgPool,
+        event: &AuthEvent,
+        client: &ClientInfo,
+    ) -> Result<(), SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO auth_events (
+                user_id, username, event_type, outcome, reason,
+                ip_address, user_agent
+            )
+            VALUES (
+                COALESCE($1, (SELECT id FROM users WHERE username = $2)),
+                $2, $3, $4, $5, $6, $7
+            )
+            "#,
+            event.user_id,
+            event.username,
+            event.event_type.as_str(),
+            event.outcome.as_str(),
+            event.reason,
+            client.ip_address,
+            client.user_agent
+        )
+        .execute(pool)
+        .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                log::error!(
+                    "Database error when recording {} event: {e}",
+                    event.event_type.as_str()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Events of the user, newest first.
+    pub async fn find_by_user(
+        pool: &PgPool,
+        user_id: i32,
+        limit: i64,
+        before_id: Option<i64>,
+    ) -> Result<Vec<AuthEventRecord>, SqlxError> {
+        let result = sqlx::query_as!(
+            AuthEventRecord,
+            r#"
+            SELECT e.id, u.public_id AS "user_id?", e.username, e.event_type,
+                e.outcome, e.reason, e.ip_address, e.user_agent, e.created_at
+            FROM auth_events e
+            LEFT JOIN users u ON u.id = e.user_id
+            WHERE e.user_id = $1
+                AND ($2::bigint IS NULL OR e.id < $2)
+            ORDER BY e.id DESC
+            LIMIT $3
+            "#,
+            user_id,
+            before_id,
+            limit
+        )
+        .fetch_all(pool)
+        .await;
+
+        match result {
+            Ok(events) => Ok(events),
+            Err(e) => {
+                log::error!(
+                    "Database error when finding events of user {user_id}: {e}"
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Events matching every filter that is set, newest first.
+    pub async fn search(
+        pool: &PgPool,
+        query: &AuthEventsQuery,
+    ) -> Result<Vec<AuthEventRecord>, SqlxError> {
+        let (public_id, id) =
+            query.user_id.map_or((None, None), PublicRef::keys);
+        let result = sqlx::query_as!(
+            AuthEventRecord,
+            r#"
+            SELECT e.id, u.public_id AS "user_id?", e.username, e.event_type,
+                e.outcome, e.reason, e.ip_address, e.user_agent, e.created_at
+            FROM auth_events e
+            LEFT JOIN users u ON u.id = e.user_id
+            WHERE ($1::uuid IS NULL OR u.public_id = $1)
+                AND ($2::int IS NULL OR e.user_id = $2)
+                AND ($3::text IS NULL OR e.username = $3)
+                AND ($4::text IS NULL OR e.event_type = $4)
+                AND ($5::text IS NULL OR e.outcome = $5)
+                AND ($6::text IS NULL OR e.ip_address = $6)
+                AND ($7::timestamptz IS NULL OR e.created_at >= $7)
+                AND ($8::timestamptz IS NULL OR e.created_at < $8)
+                AND ($9::bigint IS NULL OR e.id < $9)
+            ORDER BY e.id DESC
+            LIMIT $10
+            "#,
+            public_id,
+            id,
+            query.username,
+            query.event_type,
+            query.outcome,
+            query.ip_address,
+            query.since,
+            query.until,
+            query.before_id,
+            query.limit
+        )
+        .fetch_all(pool)
+        .await;
+
+        match result {
+            Ok(events) => Ok(events),
+            Err(e) => {
+                log::error!("Database error when searching auth events: {e}");
+                Err(e)
+            }
+        }
+    }
+}
@@ -1,107 +1,240 @@
 use anyhow::Result;
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction};
 use time::OffsetDateTime;
+use uuid::Uuid;
 
 use crate::{
-    errors::auth_errors::AuthError, models::auth_models::RefreshToken,
+    errors::auth_errors::AuthError,
+    models::auth_models::{ClientInfo, RefreshToken, RefreshTokenRecord},
+    services::token_hash_services::TokenHashService,
 };
 
 pub struct AuthRepository;
 
 impl AuthRepository {
-    pub async fn validate_refresh_token(
-        pool: &PgPool,
-        token: &str,
-    ) -> Result<i32, AuthError> {
-        let result = sqlx::query!(
+    /// Finds a token and locks its row until the transaction ends, so
+    /// concurrent rotations of the same token run one after another.
+    pub async fn lock_refresh_token(
+        tx: &mut Transaction<'_, Postgres>,
+        token_hash: &str,
+    ) -> Result<RefreshTokenRecord, AuthError> {
+        let result = sqlx::query_as!(
+            RefreshTokenRecord,
             r#"
-            SELECT user_id, expires_at
+            SELECT user_id, family_id, scope, audience, expires_at,
+                consumed_at, successor_jti
             FROM refresh_tokens
-            WHERE token = $1
+            WHERE token_hash = $1
+            FOR UPDATE
             "#,
-            token
+            token_hash
         )
-        .fetch_optional(pool)
+        .fetch_optional(&mut **tx)
         .await;
 
         match result {
             Ok(Some(record)) => {
-                let expires_at: OffsetDateTime = record.expires_at;
-                if expires_at < OffsetDateTime::now_utc() {
-                    log::warn!(
-                        "Refresh token expired for user {}",
-                        record.user_id
-                    );
-                    Err(AuthError::TokenExpired)
-                } else {
-                    log::debug!(
-                        "Refresh token validated for user {}",
-                        record.user_id
-                    );
-                    Ok(record.user_id)
-                }
+                log::debug!("Refresh token found for user {}", record.user_id);
+                Ok(record)
             }
             Ok(None) => {
-                log::warn!("Refresh token not found: {}", token);
+                log::warn!(
+                    "Refresh token not found: {}",
+                    TokenHashService::fingerprint(token_hash)
+                );
                 Err(AuthError::RefreshTokenNotFound)
             }
             Err(e) => {
                 log::error!(
-                    "Database error when validating refresh token: {}",
-                    e
+                    "Database error when validating refresh token: {e}"
                 );
                 Err(AuthError::Authentication(e.to_string()))
             }
         }
     }
 
+    /// Locks the token like [`Self::lock_refresh_token`] and returns
+    /// whether it exists and has not been rotated yet.
+    pub async fn lock_unconsumed(
+        tx: &mut Transaction<'_, Postgres>,
+        token_hash: &str,
+    ) -> Result<bool, AuthError> {
+        let result = sqlx::query_scalar!(
+            r#"
+            SELECT consumed_at IS NULL AS "unconsumed!"
+            FROM refresh_tokens
+            WHERE token_hash = $1
+            FOR UPDATE
+            "#,
+            token_hash
+        )
+        .fetch_optional(&mut **tx)
+        .await;
+
+        match result {
+            Ok(unconsumed) => Ok(unconsumed.unwrap_or(false)),
+            Err(e) => {
+                log::error!("Database error when locking refresh token: {e}");
+                Err(AuthError::Database(e))
+            }
+        }
+    }
+
+    /// Marks a token as used by rotation and records the access token it
+    /// was rotated to. Returns `false` when it had already been consumed.
+    pub async fn consume_refresh_token(
+        tx: &mut Transaction<'_, Postgres>,
+        token_hash: &str,
+        consumed_at: OffsetDateTime,
+        successor_jti: Uuid,
+    ) -> Result<bool, AuthError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE refresh_tokens
+            SET consumed_at = $2, successor_jti = $3
+            WHERE token_hash = $1 AND consumed_at IS NULL
+            "#,
+            token_hash,
+            consumed_at,
+            successor_jti
+        )
+        .execute(&mut **tx)
+        .await;
+
+        match result {
+            Ok(res) => Ok(res.rows_affected() > 0),
+            Err(e) => {
+                log::error!("Database error when consuming refresh token: {e}");
+                Err(AuthError::Authentication(e.to_string()))
+            }
+        }
+    }
+
+    /// Deletes every token of the family the given token belongs to and
+    /// returns the user it belonged to.
     pub async fn delete_refresh_token(
         pool: &PgPool,
-        token: &str,
-    ) -> Result<(), AuthError> {
-        let result = sqlx::query!(
+        token_hash: &str,
+    ) -> Result<i32, AuthError> {
+        let result = sqlx::query_scalar!(
             r#"
                 DELETE FROM refresh_tokens
-                WHERE token = $1
+                WHERE family_id = (
+                    SELECT family_id FROM refresh_tokens WHERE token_hash = $1
+                )
+                RETURNING user_id
                 "#,
-            token
+            token_hash
         )
-        .execute(pool)
+        .fetch_all(pool)
         .await;
 
+        let fingerprint = TokenHashService::fingerprint(token_hash);
         match result {
-            Ok(res) if res.rows_affected() > 0 => {
-                log::info!("Refresh token deleted: {}", token);
-                Ok(())
+            Ok(user_ids) if !user_ids.is_empty() => {
+                log::info!("Refresh token deleted: {fingerprint}");
+                Ok(user_ids[0])
             }
             Ok(_) => {
-                log::warn!("Refresh token not found for deletion: {}", token);
+                log::warn!(
+                    "Refresh token not found for deletion: {fingerprint}"
+                );
                 Err(AuthError::RefreshTokenNotFound)
             }
+            Err(e) => {
+                log::error!("Database error when deleting refresh token: {e}");
+                Err(AuthError::Authentication(e.to_string()))
+            }
+        }
+    }
+
+    pub async fn revoke_refresh_token_family(
+        pool: &PgPool,
+        family_id: Uuid,
+    ) -> Result<u64, AuthError> {
+        let result = sqlx::query!(
+            "DELETE FROM refresh_tokens WHERE family_id = $1",
+            family_id
+        )
+        .execute(pool)
+        .await;
+
+        match result {
+            Ok(res) => {
+                log::info!(
+                    "Revoked {} refresh tokens of family {family_id}",
+                    res.rows_affected()
+                );
+                Ok(res.rows_affected())
+            }
             Err(e) => {
                 log::error!(
-                    "Database error when deleting refresh token: {}",
-                    e
+                    "Database error when revoking refresh token family {family_id}: {e}"
                 );
-                Err(AuthError::Authentication(e.to_string()))
+                Err(AuthError::Database(e))
             }
         }
     }
 
-    pub async fn save_refresh_token(
+    pub async fn record_refresh_token_reuse(
         pool: &PgPool,
+        user_id: i32,
+        family_id: Uuid,
+    ) -> Result<(), AuthError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO refresh_token_reuse_events (user_id, family_id)
+            VALUES ($1, $2)
+            "#,
+            user_id,
+            family_id
+        )
+        .execute(pool)
+        .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                log::error!(
+                    "Failed to record refresh token reuse for user {user_id}: {e}"
+                );
+                Err(AuthError::Database(e))
+            }
+        }
+    }
+
+    pub async fn save_refresh_token(
+        executor: impl PgExecutor<'_>,
         token: &RefreshToken,
+        client: &ClientInfo,
     ) -> Result<(), AuthError> {
+        // A rotated token inherits the start time of its session
         let result = sqlx::query!(
             r#"
-            INSERT INTO refresh_tokens (token, user_id, expires_at)
-            VALUES ($1, $2, $3)
+            INSERT INTO refresh_tokens (
+                token_hash, user_id, family_id, expires_at,
+                session_created_at, user_agent, ip_address, scope, audience
+            )
+            VALUES (
+                $1, $2, $3, $4,
+                COALESCE(
+                    (SELECT MIN(session_created_at) FROM refresh_tokens
+                     WHERE family_id = $3),
+                    NOW()
+                ),
+                $5, $6, $7, $8
+            )
             "#,
-            token.token,
+            token.token_hash,
             token.user_id as i32,
-            token.expires_at
+            token.family_id,
+            token.expires_at,
+            client.user_agent,
+            client.ip_address,
+            token.scope,
+            token.audience
         )
-        .execute(pool)
+        .execute(executor)
         .await;
 
         match result {
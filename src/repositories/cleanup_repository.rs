@@ -0,0 +1,143 @@
+use sqlx::{Error as SqlxError, Postgres, Transaction};
+use time::OffsetDateTime;
+
+use crate::config::app_config::CleanupJob;
+
+/// Deletes rows that can no longer be used, one table per job.
+pub struct CleanupRepository;
+
+impl CleanupRepository {
+    /// Takes the advisory lock of the job until the transaction ends.
+    /// Returns `false` if another instance is running it.
+    pub async fn try_lock(
+        tx: &mut Transaction<'_, Postgres>,
+        job: CleanupJob,
+    ) -> Result<bool, SqlxError> {
+        let result = sqlx::query_scalar!(
+            r#"SELECT pg_try_advisory_xact_lock(hashtext($1)) AS "locked!""#,
+            format!("actix_jwt_auth.cleanup.{}", job.as_str())
+        )
+        .fetch_one(&mut **tx)
+        .await;
+
+        match result {
+            Ok(locked) => Ok(locked),
+            Err(e) => {
+                log::error!("Failed to lock cleanup job {}: {e}", job.as_str());
+                Err(e)
+            }
+        }
+    }
+
+    /// Notes when the job last ran, for operators. It does not decide
+    /// whether a job runs, the advisory lock does.
+    pub async fn record_run(
+        tx: &mut Transaction<'_, Postgres>,
+        job: CleanupJob,
+    ) -> Result<(), SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO cleanup_runs (job, last_run_at)
+            VALUES ($1, now())
+            ON CONFLICT (job) DO UPDATE SET last_run_at = EXCLUDED.last_run_at
+            "#,
+            job.as_str()
+        )
+        .execute(&mut **tx)
+        .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                log::error!(
+                    "Failed to record run of cleanup job {}: {e}",
+                    job.as_str()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Deletes the rows of the job's table that expired before `before`.
+    /// Returns how many were deleted.
+    pub async fn purge(
+        tx: &mut Transaction<'_, Postgres>,
+        job: CleanupJob,
+        before: OffsetDateTime,
+    ) -> Result<u64, SqlxError> {
+        let result =
+            match job {
+                CleanupJob::RefreshTokens => {
+                    sqlx::query!(
+                        "DELETE FROM refresh_tokens WHERE expires_at < $1",
+                        before
+                    )
+                    .execute(&mut **tx)
+                    .await
+                }
+                CleanupJob::RevokedAccessTokens => sqlx::query!(
+                    "DELETE FROM revoked_access_tokens WHERE expires_at < $1",
+                    before
+                )
+                .execute(&mut **tx)
+                .await,
+                CleanupJob::AccessTokenCutoffs => sqlx::query!(
+                    "DELETE FROM access_token_cutoffs WHERE not_before < $1",
+                    before
+                )
+                .execute(&mut **tx)
+                .await,
+                // GREATEST пропускает NULL, так что незаблокированные строки
+                // сравниваются по последней ошибке
+                CleanupJob::LoginThrottles => {
+                    sqlx::query!(
+                        r#"
+                    DELETE FROM login_throttles
+                    WHERE GREATEST(last_failure_at, locked_until) < $1
+                    "#,
+                        before
+                    )
+                    .execute(&mut **tx)
+                    .await
+                }
+                CleanupJob::MfaChallenges => {
+                    sqlx::query!(
+                        "DELETE FROM mfa_challenges WHERE expires_at < $1",
+                        before
+                    )
+                    .execute(&mut **tx)
+                    .await
+                }
+                CleanupJob::UserTokens => {
+                    sqlx::query!(
+                        "DELETE FROM user_tokens WHERE expires_at < $1",
+                        before
+                    )
+                    .execute(&mut **tx)
+                    .await
+                }
+                CleanupJob::PersonalAccessTokens => {
+                    sqlx::query!(
+                        r#"
+                    DELETE FROM personal_access_tokens
+                    WHERE revoked_at < $1 OR expires_at < $1
+                    "#,
+                        before
+                    )
+                    .execute(&mut **tx)
+                    .await
+                }
+            };
+
+        match result {
+            Ok(res) => Ok(res.rows_affected()),
+            Err(e) => {
+                log::error!(
+                    "Database error when purging {}: {e}",
+                    job.as_str()
+                );
+                Err(e)
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use sqlx::{Error as SqlxError, PgPool};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// Storage of revoked access tokens. Errors are returned as plain database
+/// errors since users, sessions and auth all revoke tokens.
+pub struct DenylistRepository;
+
+impl DenylistRepository {
+    pub async fn revoke_jti(
+        pool: &PgPool,
+        jti: Uuid,
+        user_id: i32,
+        expires_at: i64,
+    ) -> Result<(), SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO revoked_access_tokens (jti, user_id, expires_at)
+            VALUES ($1, $2, TO_TIMESTAMP($3::BIGINT))
+            ON CONFLICT (jti) DO NOTHING
+            "#,
+            jti,
+            user_id,
+            expires_at
+        )
+        .execute(pool)
+        .await;
+
+        match result {
+            Ok(_) => {
+                log::info!("Access token {jti} of user {user_id} revoked");
+                Ok(())
+            }
+            Err(e) => {
+                log::error!("Failed to revoke access token {jti}: {e}");
+                Err(e)
+            }
+        }
+    }
+
+    /// Invalidates every access token issued to the user before
+    /// `not_before`.
+    pub async fn revoke_user_tokens(
+        pool: &PgPool,
+        user_id: i32,
+        not_before: OffsetDateTime,
+    ) -> Result<(), SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO access_token_cutoffs (user_id, not_before)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE SET not_before = EXCLUDED.not_before
+            "#,
+            user_id,
+            not_before
+        )
+        .execute(pool)
+        .await;
+
+        match result {
+            Ok(_) => {
+                log::info!("Access tokens of user {user_id} revoked");
+                Ok(())
+            }
+            Err(e) => {
+                log::error!(
+                    "Failed to revoke access tokens of user {user_id}: {e}"
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Revoked tokens expiring after `expires_after`; earlier ones are
+    /// rejected by their `exp` anyway.
+    pub async fn find_revoked_jtis(
+        pool: &PgPool,
+        expires_after: OffsetDateTime,
+    ) -> Result<Vec<Uuid>, SqlxError> {
+        let result = sqlx::query_scalar!(
+            "SELECT jti FROM revoked_access_tokens WHERE expires_at > $1",
+            expires_after
+        )
+        .fetch_all(pool)
+        .await;
+
+        match result {
+            Ok(jtis) => Ok(jtis),
+            Err(e) => {
+                log::error!("Failed to load revoked access tokens: {e}");
+                Err(e)
+            }
+        }
+    }
+
+    /// Cutoffs set after `since`; older ones no longer affect any unexpired
+    /// token.
+    pub async fn find_cutoffs(
+        pool: &PgPool,
+        since: OffsetDateTime,
+    ) -> Result<Vec<(i32, OffsetDateTime)>, SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            SELECT user_id, not_before
+            FROM access_token_cutoffs
+            WHERE not_before > $1
+            "#,
+            since
+        )
+        .fetch_all(pool)
+        .await;
+
+        match result {
+            Ok(rows) => Ok(rows
+                .into_iter()
+                .map(|row| (row.user_id, row.not_before))
+                .collect()),
+            Err(e) => {
+                log::error!("Failed to load access token cutoffs: {e}");
+                Err(e)
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use sqlx::{Error as SqlxError, PgPool};
+use time::OffsetDateTime;
+
+use crate::models::login_throttle_models::ThrottleScope;
+
+/// Failed login counters, shared by every instance of the service.
+pub struct LoginThrottleRepository;
+
+impl LoginThrottleRepository {
+    /// Latest lock still in effect for the username or the IP.
+    pub async fn find_locked_until(
+        pool: &PgPool,
+        username: &str,
+        ip_address: Option<&str>,
+    ) -> Result<Option<OffsetDateTime>, SqlxError> {
+        let result = sqlx::query_scalar!(
+            r#"
+            SELECT MAX(locked_until)
+            FROM login_throttles
+            WHERE locked_until > NOW()
+                AND ((scope = $1 AND subject = $2)
+                    OR (scope = $3 AND subject = $4))
+            "#,
+            ThrottleScope::Username.as_str(),
+            username,
+            ThrottleScope::Ip.as_str(),
+            ip_address
+        )
+        .fetch_one(pool)
+        .await;
+
+        match result {
+            Ok(locked_until) => Ok(locked_until),
+            Err(e) => {
+                log::error!("Database error when checking login lock: {e}");
+                Err(e)
+            }
+        }
+    }
+
+    /// Counts a failure and returns the number of failures so far. The
+    /// counter starts over when the previous failure is older than
+    /// `reset_after_secs`.
+    pub async fn record_failure(
+        pool: &PgPool,
+        scope: ThrottleScope,
+        subject: &str,
+        reset_after_secs: i64,
+    ) -> Result<i32, SqlxError> {
+        let result = sqlx::query_scalar!(
+            r#"
+            INSERT INTO login_throttles (scope, subject, failures)
+            VALUES ($1, $2, 1)
+            ON CONFLICT (scope, subject) DO UPDATE SET
+                failures = CASE
+                    WHEN login_throttles.last_failure_at
+                        < NOW() - MAKE_INTERVAL(secs => $3::BIGINT)
+                    THEN 1
+                    ELSE login_throttles.failures + 1
+                END,
+                last_failure_at = NOW()
+            RETURNING failures
+            "#,
+            scope.as_str(),
+            subject,
+            reset_after_secs
+        )
+        .fetch_one(pool)
+        .await;
+
+        match result {
+            Ok(failures) => {
+                log::warn!(
+                    "Failed login #{failures} for {} '{subject}'",
+                    scope.as_str()
+                );
+                Ok(failures)
+            }
+            Err(e) => {
+                log::error!("Failed to record login failure: {e}");
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn lock(
+        pool: &PgPool,
+        scope: ThrottleScope,
+        subject: &str,
+        locked_until: OffsetDateTime,
+    ) -> Result<(), SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE login_throttles
+            SET locked_until = $3
+            WHERE scope = $1 AND subject = $2
+            "#,
+            scope.as_str(),
+            subject,
+            locked_until
+        )
+        .execute(pool)
+        .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                log::error!(
+                    "Failed to lock {} '{subject}': {e}",
+                    scope.as_str()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Removes the counter. Returns `true` if there was one.
+    pub async fn clear(
+        pool: &PgPool,
+        scope: ThrottleScope,
+        subject: &str,
+    ) -> Result<bool, SqlxError> {
+        let result = sqlx::query!(
+            "DELETE FROM login_throttles WHERE scope = $1 AND subject = $2",
+            scope.as_str(),
+            subject
+        )
+        .execute(pool)
+        .await;
+
+        match result {
+            Ok(res) => Ok(res.rows_affected() > 0),
+            Err(e) => {
+                log::error!(
+                    "Failed to clear login failures of {} '{subject}': {e}",
+                    scope.as_str()
+                );
+                Err(e)
+            }
+        }
+    }
+}
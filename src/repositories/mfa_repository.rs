@@ -0,0 +1,316 @@
+use sqlx::{Error as SqlxError, PgPool};
+use time::OffsetDateTime;
+
+use crate::models::mfa_models::{MfaChallengeRecord, TotpState};
+
+/// TOTP secrets, recovery codes and pending login challenges. Errors are
+/// returned as plain database errors since both the login flow and the
+/// enrollment endpoints use it.
+pub struct MfaRepository;
+
+impl MfaRepository {
+    pub async fn find_totp(
+        pool: &PgPool,
+        user_id: i32,
+    ) -> Result<Option<TotpState>, SqlxError> {
+        let result = sqlx::query_as!(
+            TotpState,
+            r#"
+            SELECT username, totp_secret, totp_enabled
+            FROM users
+            WHERE id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(pool)
+        .await;
+
+        match result {
+            Ok(state) => Ok(state),
+            Err(e) => {
+                log::error!(
+                    "Database error when loading TOTP of user {user_id}: {e}"
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Stores a secret awaiting confirmation. Returns `false` if 2FA is
+    /// already enabled, an active secret is never replaced.
+    pub async fn set_pending_secret(
+        pool: &PgPool,
+        user_id: i32,
+        secret: &str,
+    ) -> Result<bool, SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET totp_secret = $2, totp_last_step = NULL
+            WHERE id = $1 AND totp_enabled = FALSE
+            "#,
+            user_id,
+            secret
+        )
+        .execute(pool)
+        .await;
+
+        match result {
+            Ok(res) => Ok(res.rows_affected() > 0),
+            Err(e) => {
+                log::error!(
+                    "Database error when enrolling TOTP of user {user_id}: {e}"
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Enables 2FA and stores the recovery codes in one transaction.
+    pub async fn enable(
+        pool: &PgPool,
+        user_id: i32,
+        step: i64,
+        recovery_code_hashes: &[String],
+    ) -> Result<bool, SqlxError> {
+        let mut tx = pool.begin().await?;
+
+        let enabled = sqlx::query!(
+            r#"
+            UPDATE users
+            SET totp_enabled = TRUE, totp_last_step = $2
+            WHERE id = $1 AND totp_secret IS NOT NULL AND totp_enabled = FALSE
+            "#,
+            user_id,
+            step
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+        if !enabled {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            "DELETE FROM mfa_recovery_codes WHERE user_id = $1",
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO mfa_recovery_codes (user_id, code_hash)
+            SELECT $1, UNNEST($2::TEXT[])
+            "#,
+            user_id,
+            recovery_code_hashes
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        log::info!("TOTP enabled for user {user_id}");
+        Ok(true)
+    }
+
+    pub async fn disable(pool: &PgPool, user_id: i32) -> Result<(), SqlxError> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET totp_secret = NULL, totp_enabled = FALSE, totp_last_step = NULL
+            WHERE id = $1
+            "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            "DELETE FROM mfa_recovery_codes WHERE user_id = $1",
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        log::info!("TOTP disabled for user {user_id}");
+        Ok(())
+    }
+
+    /// Records `step` as used. Returns `false` if a code of this or a later
+    /// step was already accepted, which rejects replayed codes.
+    pub async fn use_step(
+        pool: &PgPool,
+        user_id: i32,
+        step: i64,
+    ) -> Result<bool, SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET totp_last_step = $2
+            WHERE id = $1
+                AND (totp_last_step IS NULL OR totp_last_step < $2)
+            "#,
+            user_id,
+            step
+        )
+        .execute(pool)
+        .await;
+
+        match result {
+            Ok(res) => Ok(res.rows_affected() > 0),
+            Err(e) => {
+                log::error!("Database error when using TOTP step: {e}");
+                Err(e)
+            }
+        }
+    }
+
+    /// Marks an unused recovery code as used. Returns `false` if there is
+    /// no such code.
+    pub async fn use_recovery_code(
+        pool: &PgPool,
+        user_id: i32,
+        code_hash: &str,
+    ) -> Result<bool, SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE mfa_recovery_codes
+            SET used_at = NOW()
+            WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+            "#,
+            user_id,
+            code_hash
+        )
+        .execute(pool)
+        .await;
+
+        match result {
+            Ok(res) if res.rows_affected() > 0 => {
+                log::warn!("Recovery code used by user {user_id}");
+                Ok(true)
+            }
+            Ok(_) => Ok(false),
+            Err(e) => {
+                log::error!("Database error when using recovery code: {e}");
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn create_challenge(
+        pool: &PgPool,
+        token_hash: &str,
+        user_id: i32,
+        scope: Option<&str>,
+        audience: Option<&str>,
+        expires_at: OffsetDateTime,
+    ) -> Result<(), SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO mfa_challenges (
+                token_hash, user_id, scope, audience, expires_at
+            )
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            token_hash,
+            user_id,
+            scope,
+            audience,
+            expires_at
+        )
+        .execute(pool)
+        .await;
+
+        match result {
+            Ok(_) => {
+                log::info!("MFA challenge issued for user {user_id}");
+                Ok(())
+            }
+            Err(e) => {
+                log::error!(
+                    "Failed to save MFA challenge for user {user_id}: {e}"
+                );
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn find_challenge(
+        pool: &PgPool,
+        token_hash: &str,
+    ) -> Result<Option<MfaChallengeRecord>, SqlxError> {
+        let result = sqlx::query_as!(
+            MfaChallengeRecord,
+            r#"
+            SELECT c.user_id, u.username, c.scope, c.audience, c.attempts,
+                c.expires_at
+            FROM mfa_challenges c
+            JOIN users u ON u.id = c.user_id
+            WHERE c.token_hash = $1
+            "#,
+            token_hash
+        )
+        .fetch_optional(pool)
+        .await;
+
+        match result {
+            Ok(record) => Ok(record),
+            Err(e) => {
+                log::error!("Database error when finding MFA challenge: {e}");
+                Err(e)
+            }
+        }
+    }
+
+    /// Counts a wrong code and returns the attempts made so far.
+    pub async fn record_challenge_failure(
+        pool: &PgPool,
+        token_hash: &str,
+    ) -> Result<i32, SqlxError> {
+        let result = sqlx::query_scalar!(
+            r#"
+            UPDATE mfa_challenges
+            SET attempts = attempts + 1
+            WHERE token_hash = $1
+            RETURNING attempts
+            "#,
+            token_hash
+        )
+        .fetch_optional(pool)
+        .await;
+
+        match result {
+            // Удалён параллельным запросом
+            Ok(attempts) => Ok(attempts.unwrap_or(i32::MAX)),
+            Err(e) => {
+                log::error!("Database error when counting MFA attempt: {e}");
+                Err(e)
+            }
+        }
+    }
+
+    /// Removes the challenge. Returns `false` if it was already gone, so
+    /// only one request can redeem it.
+    pub async fn delete_challenge(
+        pool: &PgPool,
+        token_hash: &str,
+    ) -> Result<bool, SqlxError> {
+        let result = sqlx::query!(
+            "DELETE FROM mfa_challenges WHERE token_hash = $1",
+            token_hash
+        )
+        .execute(pool)
+        .await;
+
+        match result {
+            Ok(res) => Ok(res.rows_affected() > 0),
+            Err(e) => {
+                log::error!("Database error when deleting MFA challenge: {e}");
+                Err(e)
+            }
+        }
+    }
+}
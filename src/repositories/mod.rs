@@ -1,3 +1,12 @@
+pub mod audit_repository;
 pub mod auth_repisitory;
+pub mod cleanup_repository;
+pub mod denylist_repository;
+pub mod login_throttle_repository;
+pub mod mfa_repository;
+pub mod personal_tokens_repository;
 pub mod posts_repository;
+pub mod roles_repository;
+pub mod sessions_repository;
+pub mod user_tokens_repository;
 pub mod users_repository;
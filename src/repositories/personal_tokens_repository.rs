@@ -0,0 +1,165 @@
+use sqlx::{Error as SqlxError, PgPool};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::models::personal_tokens_models::{
+    PersonalToken, PersonalTokenRecord,
+};
+
+/// Personal access tokens, stored as hashes. Errors are returned as plain
+/// database errors since both the token endpoints and the auth middleware
+/// use it.
+pub struct PersonalTokensRepository;
+
+impl PersonalTokensRepository {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create(
+        pool: &PgPool,
+        id: Uuid,
+        user_id: i32,
+        name: &str,
+        token_hash: &str,
+        token_prefix: &str,
+        scopes: &[String],
+        expires_at: Option<OffsetDateTime>,
+    ) -> Result<PersonalToken, SqlxError> {
+        let result = sqlx::query_as!(
+            PersonalToken,
+            r#"
+            INSERT INTO personal_access_tokens
+                (id, user_id, name, token_hash, token_prefix, scopes, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, name, token_prefix, scopes, created_at,
+                last_used_at, expires_at
+            "#,
+            id,
+            user_id,
+            name,
+            token_hash,
+            token_prefix,
+            scopes,
+            expires_at
+        )
+        .fetch_one(pool)
+        .await;
+
+        match result {
+            Ok(token) => {
+                log::info!(
+                    "Personal access token {id} created for user {user_id}"
+                );
+                Ok(token)
+            }
+            Err(e) => {
+                log::error!(
+                    "Database error when creating a personal access token for user {user_id}: {e}"
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Tokens of the user that are neither revoked nor expired.
+    pub async fn find_all(
+        pool: &PgPool,
+        user_id: i32,
+    ) -> Result<Vec<PersonalToken>, SqlxError> {
+        let result = sqlx::query_as!(
+            PersonalToken,
+            r#"
+            SELECT id, name, token_prefix, scopes, created_at,
+                last_used_at, expires_at
+            FROM personal_access_tokens
+            WHERE user_id = $1
+                AND revoked_at IS NULL
+                AND (expires_at IS NULL OR expires_at > NOW())
+            ORDER BY created_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await;
+
+        match result {
+            Ok(tokens) => Ok(tokens),
+            Err(e) => {
+                log::error!(
+                    "Database error when finding personal access tokens of user {user_id}: {e}"
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Looks up a valid token by its hash and records that it was used.
+    pub async fn use_token(
+        pool: &PgPool,
+        token_hash: &str,
+    ) -> Result<Option<PersonalTokenRecord>, SqlxError> {
+        let result = sqlx::query_as!(
+            PersonalTokenRecord,
+            r#"
+            UPDATE personal_access_tokens AS t
+            SET last_used_at = NOW()
+            FROM users u
+            WHERE t.token_hash = $1
+                AND t.revoked_at IS NULL
+                AND (t.expires_at IS NULL OR t.expires_at > NOW())
+                AND u.id = t.user_id
+            RETURNING
+                t.id, t.user_id, u.public_id, t.scopes, t.created_at,
+                t.expires_at
+            "#,
+            token_hash
+        )
+        .fetch_optional(pool)
+        .await;
+
+        match result {
+            Ok(record) => Ok(record),
+            Err(e) => {
+                log::error!(
+                    "Database error when looking up a personal access token: {e}"
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns `false` if the user has no such live token.
+    pub async fn revoke(
+        pool: &PgPool,
+        user_id: i32,
+        token_id: Uuid,
+    ) -> Result<bool, SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE personal_access_tokens
+            SET revoked_at = NOW()
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+            "#,
+            token_id,
+            user_id
+        )
+        .execute(pool)
+        .await;
+
+        match result {
+            Ok(res) => {
+                let revoked = res.rows_affected() > 0;
+                if revoked {
+                    log::info!(
+                        "Personal access token {token_id} of user {user_id} revoked"
+                    );
+                }
+                Ok(revoked)
+            }
+            Err(e) => {
+                log::error!(
+                    "Database error when revoking personal access token {token_id}: {e}"
+                );
+                Err(e)
+            }
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use crate::{
     errors::posts_errors::PostError,
-    models::posts_models::{CreatePost, Post, UpdatePost},
+    models::{
+        posts_models::{CreatePost, Post, UpdatePost},
+        public_id_models::PublicRef,
+    },
 };
 use anyhow::Result;
 use sqlx::PgPool;
@@ -11,7 +14,7 @@ impl PostsRepository {
     pub async fn create(
         pool: &PgPool,
         new_post: CreatePost,
-        user_id: i32
+        user_id: i32,
     ) -> Result<Post, PostError> {
         //TODO Need to create validation before INSERT in DB (because PSQL creating index in both cases)
 
@@ -22,8 +25,10 @@ impl PostsRepository {
             VALUES ($1, $2)
             RETURNING 
             id, 
+            public_id,
             message, 
             user_id, 
+            (SELECT public_id FROM users WHERE users.id = posts.user_id) AS "author_id!",
             created_at,
             updated_at
             "#,
@@ -62,19 +67,24 @@ impl PostsRepository {
 
     pub async fn get_all(
         pool: &PgPool,
-        user_id: i32,
+        author: PublicRef,
     ) -> Result<Vec<Post>, PostError> {
+        let (author_id, user_id) = author.keys();
         let result = sqlx::query_as!(
             Post,
             "SELECT 
-                id, 
-                message, 
-                user_id, 
-                created_at,
-                updated_at
+                posts.id, 
+                posts.public_id,
+                posts.message, 
+                posts.user_id, 
+                users.public_id AS author_id,
+                posts.created_at,
+                posts.updated_at
             FROM posts
-            WHERE user_id = $1
-            ORDER BY created_at DESC",
+            JOIN users ON users.id = posts.user_id
+            WHERE users.public_id = $1 OR users.id = $2
+            ORDER BY posts.created_at DESC",
+            author_id,
             user_id
         )
         .fetch_all(pool)
@@ -82,7 +92,7 @@ impl PostsRepository {
 
         match result {
             Ok(posts) => {
-                log::info!("Posts successfully finded",);
+                log::info!("Posts successfully finded");
                 Ok(posts)
             }
             Err(e) => {
@@ -92,17 +102,25 @@ impl PostsRepository {
         }
     }
 
-    pub async fn find_by_id(pool: &PgPool, id: i32) -> Result<Post, PostError> {
+    pub async fn find(
+        pool: &PgPool,
+        post: PublicRef,
+    ) -> Result<Post, PostError> {
+        let (public_id, id) = post.keys();
         let result = sqlx::query_as!(
             Post,
             "SELECT 
-                id, 
-                message, 
-                user_id, 
-                created_at,
-                updated_at
+                posts.id, 
+                posts.public_id,
+                posts.message, 
+                posts.user_id, 
+                users.public_id AS author_id,
+                posts.created_at,
+                posts.updated_at
             FROM posts
-            WHERE id = $1",
+            JOIN users ON users.id = posts.user_id
+            WHERE posts.public_id = $1 OR posts.id = $2",
+            public_id,
             id
         )
         .fetch_optional(pool)
@@ -112,17 +130,17 @@ impl PostsRepository {
             Ok(Some(post)) => {
                 log::info!(
                     "Post {} successfully finded '{}'",
-                    id,
+                    post.id,
                     post.user_id
                 );
                 Ok(post)
             }
             Ok(None) => {
-                log::error!("Post {id} disappeared during finding");
+                log::error!("Post {post} disappeared during finding");
                 Err(PostError::NotFound)
             }
             Err(e) => {
-                log::error!("Database error when finding post {id}: {e}");
+                log::error!("Database error when finding post {post}: {e}");
                 Err(PostError::Database(e))
             }
         }
@@ -140,8 +158,10 @@ impl PostsRepository {
                 WHERE id = $2
                 RETURNING 
                     id, 
+                    public_id,
                     message, 
                     user_id, 
+                    (SELECT public_id FROM users WHERE users.id = posts.user_id) AS \"author_id!\",
                     created_at,
                     updated_at",
             post_data.message,
@@ -175,15 +195,12 @@ impl PostsRepository {
             .execute(pool)
             .await;
 
-        match result {
-            Err(e) => {
-                log::error!("Database error when deleting user {post_id}: {e}");
-                Err(PostError::Database(e))
-            }
-            _ => {
-                log::info!("Post {post_id} deleted");
-                Ok(())
-            }
+        if let Err(e) = result {
+            log::error!("Database error when deleting post {post_id}: {e}");
+            return Err(PostError::Database(e));
         }
+
+        log::info!("Post {post_id} deleted");
+        Ok(())
     }
 }
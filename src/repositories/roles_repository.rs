@@ -0,0 +1,187 @@
+use sqlx::{Error as SqlxError, PgPool};
+
+use crate::models::role_models::RolePermissions;
+
+/// Role assignments. Errors are returned as plain database errors since
+/// token issuing, user creation and the admin endpoints all use it.
+pub struct RolesRepository;
+
+impl RolesRepository {
+    /// Role names of the user, sorted so tokens are stable.
+    pub async fn find_user_roles(
+        pool: &PgPool,
+        user_id: i32,
+    ) -> Result<Vec<String>, SqlxError> {
+        let result = sqlx::query_scalar!(
+            r#"
+            SELECT roles.name
+            FROM user_roles
+            JOIN roles ON roles.id = user_roles.role_id
+            WHERE user_roles.user_id = $1
+            ORDER BY roles.name
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await;
+
+        match result {
+            Ok(roles) => Ok(roles),
+            Err(e) => {
+                log::error!(
+                    "Database error when loading roles of user {user_id}: {e}"
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Permission names granted by the roles of the user, sorted and
+    /// without duplicates.
+    pub async fn find_user_permissions(
+        pool: &PgPool,
+        user_id: i32,
+    ) -> Result<Vec<String>, SqlxError> {
+        let result = sqlx::query_scalar!(
+            r#"
+            SELECT DISTINCT permissions.name
+            FROM user_roles
+            JOIN role_permissions
+                ON role_permissions.role_id = user_roles.role_id
+            JOIN permissions ON permissions.id = role_permissions.permission_id
+            WHERE user_roles.user_id = $1
+            ORDER BY permissions.name
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await;
+
+        match result {
+            Ok(permissions) => Ok(permissions),
+            Err(e) => {
+                log::error!(
+                    "Database error when loading permissions of user {user_id}: {e}"
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Every role with its permissions, by name.
+    pub async fn find_roles(
+        pool: &PgPool,
+    ) -> Result<Vec<RolePermissions>, SqlxError> {
+        let result = sqlx::query_as!(
+            RolePermissions,
+            r#"
+            SELECT
+                roles.name,
+                roles.description,
+                COALESCE(
+                    array_agg(permissions.name ORDER BY permissions.name)
+                        FILTER (WHERE permissions.name IS NOT NULL),
+                    '{}'
+                ) AS "permissions!"
+            FROM roles
+            LEFT JOIN role_permissions ON role_permissions.role_id = roles.id
+            LEFT JOIN permissions
+                ON permissions.id = role_permissions.permission_id
+            GROUP BY roles.id
+            ORDER BY roles.name
+            "#
+        )
+        .fetch_all(pool)
+        .await;
+
+        match result {
+            Ok(roles) => Ok(roles),
+            Err(e) => {
+                log::error!("Database error when listing roles: {e}");
+                Err(e)
+            }
+        }
+    }
+
+    /// Grants the role. Returns `None` if the user or the role does not
+    /// exist, otherwise whether the user did not have it yet.
+    pub async fn grant(
+        pool: &PgPool,
+        user_id: i32,
+        role: &str,
+    ) -> Result<Option<bool>, SqlxError> {
+        let result = sqlx::query_scalar!(
+            r#"
+            WITH role AS (SELECT id FROM roles WHERE name = $2),
+            granted AS (
+                INSERT INTO user_roles (user_id, role_id)
+                SELECT $1, id FROM role
+                ON CONFLICT DO NOTHING
+                RETURNING role_id
+            )
+            SELECT EXISTS (SELECT 1 FROM granted) AS "granted!"
+            FROM role
+            "#,
+            user_id,
+            role
+        )
+        .fetch_optional(pool)
+        .await;
+
+        match result {
+            Ok(granted) => {
+                if granted == Some(true) {
+                    log::info!("Role '{role}' granted to user {user_id}");
+                }
+                Ok(granted)
+            }
+            Err(SqlxError::Database(e)) if e.is_foreign_key_violation() => {
+                log::warn!("Role '{role}' granted to unknown user {user_id}");
+                Ok(None)
+            }
+            Err(e) => {
+                log::error!(
+                    "Failed to grant role '{role}' to user {user_id}: {e}"
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns whether the user had the role.
+    pub async fn revoke(
+        pool: &PgPool,
+        user_id: i32,
+        role: &str,
+    ) -> Result<bool, SqlxError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM user_roles
+            USING roles
+            WHERE user_roles.role_id = roles.id
+                AND user_roles.user_id = $1
+                AND roles.name = $2
+            "#,
+            user_id,
+            role
+        )
+        .execute(pool)
+        .await;
+
+        match result {
+            Ok(res) => {
+                let revoked = res.rows_affected() > 0;
+                if revoked {
+                    log::info!("Role '{role}' revoked from user {user_id}");
+                }
+                Ok(revoked)
+            }
+            Err(e) => {
+                log::error!(
+                    "Failed to revoke role '{role}' from user {user_id}: {e}"
+                );
+                Err(e)
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{
+    errors::sessions_errors::SessionError, models::sessions_models::Session,
+};
+use anyhow::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Sessions are refresh token families, the live (unconsumed) token of a
+/// family carries its metadata.
+pub struct SessionsRepository;
+
+impl SessionsRepository {
+    pub async fn get_all(
+        pool: &PgPool,
+        user_id: i32,
+        current_session_id: Option<Uuid>,
+    ) -> Result<Vec<Session>, SessionError> {
+        let result = sqlx::query_as!(
+            Session,
+            r#"
+            SELECT
+                family_id AS id,
+                session_created_at AS created_at,
+                created_at AS last_used_at,
+                expires_at,
+                user_agent,
+                ip_address,
+                COALESCE(family_id = $2, FALSE) AS "current!"
+            FROM refresh_tokens
+            WHERE user_id = $1
+                AND consumed_at IS NULL
+                AND expires_at > NOW()
+            ORDER BY created_at DESC
+            "#,
+            user_id,
+            current_session_id
+        )
+        .fetch_all(pool)
+        .await;
+
+        match result {
+            Ok(sessions) => {
+                log::info!(
+                    "Found {} sessions for user {user_id}",
+                    sessions.len()
+                );
+                Ok(sessions)
+            }
+            Err(e) => {
+                log::error!(
+                    "Database error when finding sessions of user {user_id}: {e}"
+                );
+                Err(SessionError::Database(e))
+            }
+        }
+    }
+
+    pub async fn delete(
+        pool: &PgPool,
+        user_id: i32,
+        session_id: Uuid,
+    ) -> Result<(), SessionError> {
+        let result = sqlx::query!(
+            "DELETE FROM refresh_tokens WHERE user_id = $1 AND family_id = $2",
+            user_id,
+            session_id
+        )
+        .execute(pool)
+        .await;
+
+        match result {
+            Ok(res) if res.rows_affected() > 0 => {
+                log::info!("Session {session_id} of user {user_id} revoked");
+                Ok(())
+            }
+            Ok(_) => {
+                log::warn!("Session {session_id} of user {user_id} not found");
+                Err(SessionError::NotFound)
+            }
+            Err(e) => {
+                log::error!(
+                    "Database error when revoking session {session_id}: {e}"
+                );
+                Err(SessionError::Database(e))
+            }
+        }
+    }
+
+    /// Revokes every session of the user except `keep_session_id`.
+    /// Returns the number of deleted refresh tokens.
+    pub async fn delete_all(
+        pool: &PgPool,
+        user_id: i32,
+        keep_session_id: Option<Uuid>,
+    ) -> Result<u64, SessionError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM refresh_tokens
+            WHERE user_id = $1
+                AND ($2::uuid IS NULL OR family_id <> $2)
+            "#,
+            user_id,
+            keep_session_id
+        )
+        .execute(pool)
+        .await;
+
+        match result {
+            Ok(res) => {
+                log::info!(
+                    "Revoked {} refresh tokens of user {user_id}",
+                    res.rows_affected()
+                );
+                Ok(res.rows_affected())
+            }
+            Err(e) => {
+                log::error!(
+                    "Database error when revoking sessions of user {user_id}: {e}"
+                );
+                Err(SessionError::Database(e))
+            }
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use sqlx::{Error as SqlxError, PgPool, Postgres, Transaction};
+use time::OffsetDateTime;
+
+use crate::models::account_models::{TokenPurpose, UserTokenRecord};
+
+/// Single-use tokens sent by mail, stored as hashes.
+pub struct UserTokensRepository;
+
+impl UserTokensRepository {
+    /// Stores a new token. Unused tokens of the same purpose are dropped,
+    /// only the latest mail works.
+    pub async fn create(
+        pool: &PgPool,
+        token_hash: &str,
+        user_id: i32,
+        purpose: TokenPurpose,
+        email: &str,
+        expires_at: OffsetDateTime,
+    ) -> Result<(), SqlxError> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM user_tokens
+            WHERE user_id = $1 AND purpose = $2 AND used_at IS NULL
+            "#,
+            user_id,
+            purpose.as_str()
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO user_tokens
+                (token_hash, user_id, purpose, email, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            token_hash,
+            user_id,
+            purpose.as_str(),
+            email,
+            expires_at
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        log::info!("{} token issued for user {user_id}", purpose.as_str());
+        Ok(())
+    }
+
+    /// Sets the new password, marks the address the token was mailed to as
+    /// verified and revokes every refresh and personal access token of the
+    /// user. Returns the user id, or `None` if the token is unknown, used or
+    /// expired.
+    pub async fn reset_password(
+        pool: &PgPool,
+        token_hash: &str,
+        password_hash: &str,
+    ) -> Result<Option<i32>, SqlxError> {
+        let mut tx = pool.begin().await?;
+
+        let Some(token) =
+            Self::consume(&mut tx, token_hash, TokenPurpose::PasswordReset)
+                .await?
+        else {
+            return Ok(None);
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET password = $2,
+                email_verified_at = CASE
+                    WHEN LOWER(email) = LOWER($3)
+                    THEN COALESCE(email_verified_at, NOW())
+                    ELSE email_verified_at
+                END
+            WHERE id = $1
+            "#,
+            token.user_id,
+            password_hash,
+            token.email
+        )
+        .execute(&mut *tx)
+        .await?;
+        let revoked = sqlx::query!(
+            "DELETE FROM refresh_tokens WHERE user_id = $1",
+            token.user_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        sqlx::query!(
+            r#"
+            UPDATE personal_access_tokens
+            SET revoked_at = NOW()
+            WHERE user_id = $1 AND revoked_at IS NULL
+            "#,
+            token.user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        log::info!(
+            "Password of user {} reset, {revoked} refresh tokens revoked",
+            token.user_id
+        );
+        Ok(Some(token.user_id))
+    }
+
+    /// Marks the address as verified if it is still the user's address.
+    /// Returns the user id, or `None` if the token is unknown, used,
+    /// expired or was sent to an address the user no longer has.
+    pub async fn verify_email(
+        pool: &PgPool,
+        token_hash: &str,
+    ) -> Result<Option<i32>, SqlxError> {
+        let mut tx = pool.begin().await?;
+
+        let Some(token) =
+            Self::consume(&mut tx, token_hash, TokenPurpose::EmailVerification)
+                .await?
+        else {
+            return Ok(None);
+        };
+
+        let verified = sqlx::query!(
+            r#"
+            UPDATE users
+            SET email_verified_at = COALESCE(email_verified_at, NOW())
+            WHERE id = $1 AND LOWER(email) = LOWER($2)
+            "#,
+            token.user_id,
+            token.email
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+
+        tx.commit().await?;
+        if verified {
+            log::info!("Email of user {} verified", token.user_id);
+            Ok(Some(token.user_id))
+        } else {
+            log::warn!(
+                "Verification token of user {} is for an old address",
+                token.user_id
+            );
+            Ok(None)
+        }
+    }
+
+    /// Username of the user a valid token was issued to, without using it.
+    pub async fn find_username(
+        pool: &PgPool,
+        token_hash: &str,
+        purpose: TokenPurpose,
+    ) -> Result<Option<String>, SqlxError> {
+        let result = sqlx::query_scalar!(
+            r#"
+            SELECT users.username
+            FROM user_tokens
+            JOIN users ON users.id = user_tokens.user_id
+            WHERE user_tokens.token_hash = $1
+                AND user_tokens.purpose = $2
+                AND user_tokens.used_at IS NULL
+                AND user_tokens.expires_at > NOW()
+            "#,
+            token_hash,
+            purpose.as_str()
+        )
+        .fetch_optional(pool)
+        .await;
+
+        match result {
+            Ok(username) => Ok(username),
+            Err(e) => {
+                log::error!(
+                    "Database error when finding {} token: {e}",
+                    purpose.as_str()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    async fn consume(
+        tx: &mut Transaction<'_, Postgres>,
+        token_hash: &str,
+        purpose: TokenPurpose,
+    ) -> Result<Option<UserTokenRecord>, SqlxError> {
+        let result = sqlx::query_as!(
+            UserTokenRecord,
+            r#"
+            UPDATE user_tokens
+            SET used_at = NOW()
+            WHERE token_hash = $1
+                AND purpose = $2
+                AND used_at IS NULL
+                AND expires_at > NOW()
+            RETURNING user_id, email
+            "#,
+            token_hash,
+            purpose.as_str()
+        )
+        .fetch_optional(&mut **tx)
+        .await;
+
+        match result {
+            Ok(Some(token)) => Ok(Some(token)),
+            Ok(None) => {
+                log::warn!("Invalid {} token", purpose.as_str());
+                Ok(None)
+            }
+            Err(e) => {
+                log::error!(
+                    "Database error when consuming {} token: {e}",
+                    purpose.as_str()
+                );
+                Err(e)
+            }
+        }
+    }
+}
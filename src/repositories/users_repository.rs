@@ -1,34 +1,69 @@
 use crate::{
     errors::users_errors::UserError,
-    models::users_models::{CreateUser, UpdateUser, User},
+    models::{
+        public_id_models::PublicRef, role_models::DEFAULT_ROLE,
+        users_models::User,
+    },
 };
 use anyhow::Result;
 use sqlx::PgPool;
+use uuid::Uuid;
 
 pub struct UserRepository;
 
 impl UserRepository {
+    /// Creates the user together with the default role.
     pub async fn create(
         pool: &PgPool,
-        user_data: CreateUser,
+        username: &str,
+        password_hash: &str,
+        email: Option<&str>,
     ) -> Result<User, UserError> {
         //TODO Need to create validation before INSERT in DB (because PSQL creating index in both cases)
 
+        let mut tx = pool.begin().await?;
         let result = sqlx::query_as!(
             User,
             r#"
-            INSERT INTO users (username, password)
-            VALUES ($1, $2)
-            RETURNING id, username, password
+            INSERT INTO users (username, password, email)
+            VALUES ($1, $2, $3)
+            RETURNING id, public_id, username, password, email
             "#,
-            user_data.username,
-            user_data.password,
+            username,
+            password_hash,
+            email,
         )
-        .fetch_optional(pool)
+        .fetch_optional(&mut *tx)
+        .await;
+
+        let user = match result {
+            Ok(Some(user)) => user,
+            Ok(None) => {
+                log::error!("User {username} disappeared during creating");
+                return Err(UserError::NotFound);
+            }
+            Err(e) => {
+                log::error!(
+                    "Database error when creating user {username}: {e}"
+                );
+                return Err(UserError::Database(e));
+            }
+        };
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO user_roles (user_id, role_id)
+            SELECT $1, id FROM roles WHERE name = $2
+            "#,
+            user.id,
+            DEFAULT_ROLE
+        )
+        .execute(&mut *tx)
         .await;
 
         match result {
-            Ok(Some(user)) => {
+            Ok(_) => {
+                tx.commit().await?;
                 log::info!(
                     "User {} successfully created '{}'",
                     user.id,
@@ -36,18 +71,9 @@ impl UserRepository {
                 );
                 Ok(user)
             }
-            Ok(None) => {
-                log::error!(
-                    "User {} disappeared during creating",
-                    user_data.username
-                );
-                Err(UserError::NotFound)
-            }
             Err(e) => {
                 log::error!(
-                    "Database error when creating user {}: {}",
-                    user_data.username,
-                    e
+                    "Failed to assign default role to user {username}: {e}"
                 );
                 Err(UserError::Database(e))
             }
@@ -55,14 +81,16 @@ impl UserRepository {
     }
 
     pub async fn get_all(pool: &PgPool) -> Result<Vec<User>, UserError> {
-        let result =
-            sqlx::query_as!(User, "SELECT id, username, password FROM users")
-                .fetch_all(pool)
-                .await;
+        let result = sqlx::query_as!(
+            User,
+            "SELECT id, public_id, username, password, email FROM users"
+        )
+        .fetch_all(pool)
+        .await;
 
         match result {
             Ok(users) => {
-                log::info!("Users successfully finded",);
+                log::info!("Users successfully finded");
                 Ok(users)
             }
             Err(e) => {
@@ -78,7 +106,7 @@ impl UserRepository {
     ) -> Result<User, UserError> {
         let result = sqlx::query_as!(
             User,
-            "SELECT id, username, password FROM users WHERE id = $1",
+            "SELECT id, public_id, username, password, email FROM users WHERE id = $1",
             user_id
         )
         .fetch_optional(pool)
@@ -104,13 +132,92 @@ impl UserRepository {
         }
     }
 
+    /// The user named in a route, by public id or deprecated integer id.
+    pub async fn find(
+        pool: &PgPool,
+        user: PublicRef,
+    ) -> Result<User, UserError> {
+        let (public_id, id) = user.keys();
+        let result = sqlx::query_as!(
+            User,
+            r#"
+            SELECT id, public_id, username, password, email
+            FROM users
+            WHERE public_id = $1 OR id = $2
+            "#,
+            public_id,
+            id
+        )
+        .fetch_optional(pool)
+        .await;
+
+        match result {
+            Ok(Some(user)) => Ok(user),
+            Ok(None) => {
+                log::warn!("User {user} not found");
+                Err(UserError::NotFound)
+            }
+            Err(e) => {
+                log::error!("Database error when finding user {user}: {e}");
+                Err(UserError::Database(e))
+            }
+        }
+    }
+
+    /// Internal id of the user with `public_id`, `None` once deleted.
+    pub async fn find_id(
+        pool: &PgPool,
+        public_id: Uuid,
+    ) -> Result<Option<i32>, UserError> {
+        let result = sqlx::query_scalar!(
+            "SELECT id FROM users WHERE public_id = $1",
+            public_id
+        )
+        .fetch_optional(pool)
+        .await;
+
+        match result {
+            Ok(id) => Ok(id),
+            Err(e) => {
+                log::error!(
+                    "Database error when resolving user {public_id}: {e}"
+                );
+                Err(UserError::Database(e))
+            }
+        }
+    }
+
+    /// Public id of the user with the internal `id`.
+    pub async fn find_public_id(
+        pool: &PgPool,
+        id: i32,
+    ) -> Result<Uuid, UserError> {
+        let result = sqlx::query_scalar!(
+            "SELECT public_id FROM users WHERE id = $1",
+            id
+        )
+        .fetch_optional(pool)
+        .await;
+
+        match result {
+            Ok(Some(public_id)) => Ok(public_id),
+            Ok(None) => Err(UserError::NotFound),
+            Err(e) => {
+                log::error!(
+                    "Database error when finding public id of user {id}: {e}"
+                );
+                Err(UserError::Database(e))
+            }
+        }
+    }
+
     pub async fn find_by_username(
         pool: &PgPool,
         username: &str,
     ) -> Result<User, UserError> {
         let result = sqlx::query_as!(
             User,
-            "SELECT id, username, password FROM users WHERE username = $1",
+            "SELECT id, public_id, username, password, email FROM users WHERE username = $1",
             username
         )
         .fetch_optional(pool)
@@ -136,17 +243,61 @@ impl UserRepository {
         }
     }
 
+    pub async fn find_by_email(
+        pool: &PgPool,
+        email: &str,
+    ) -> Result<User, UserError> {
+        let result = sqlx::query_as!(
+            User,
+            r#"
+            SELECT id, public_id, username, password, email
+            FROM users
+            WHERE LOWER(email) = LOWER($1)
+            "#,
+            email
+        )
+        .fetch_optional(pool)
+        .await;
+
+        match result {
+            Ok(Some(user)) => {
+                log::info!("User {} found by email", user.id);
+                Ok(user)
+            }
+            Ok(None) => {
+                log::warn!("No user with the requested email");
+                Err(UserError::NotFound)
+            }
+            Err(e) => {
+                log::error!("Database error when finding user by email: {e}");
+                Err(UserError::Database(e))
+            }
+        }
+    }
+
+    /// A new `email` replaces the current one and has to be verified again.
     pub async fn update(
         pool: &PgPool,
         user_id: i32,
-        user_data: UpdateUser,
+        username: &str,
+        email: Option<&str>,
     ) -> Result<User, UserError> {
         let result = sqlx::query_as!(
             User,
-            "UPDATE users SET username = $1, password = $3 WHERE id = $2 RETURNING id, username, password",
-            user_data.username,
+            r#"
+            UPDATE users
+            SET username = $1,
+                email_verified_at = CASE
+                    WHEN $3::VARCHAR IS NULL OR LOWER($3) = LOWER(email)
+                    THEN email_verified_at
+                END,
+                email = COALESCE($3, email)
+            WHERE id = $2
+            RETURNING id, public_id, username, password, email
+            "#,
+            username,
             user_id,
-            user_data.password,
+            email,
         )
         .fetch_optional(pool)
         .await;
@@ -171,20 +322,117 @@ impl UserRepository {
         }
     }
 
-    pub async fn delete(pool: &PgPool, user_id: i32) -> Result<(), UserError> {
-        let result = sqlx::query!("DELETE FROM users WHERE id = $1", user_id)
-            .execute(pool)
-            .await;
+    pub async fn update_password_hash(
+        pool: &PgPool,
+        user_id: i32,
+        password_hash: &str,
+    ) -> Result<(), UserError> {
+        let result = sqlx::query!(
+            "UPDATE users SET password = $1 WHERE id = $2",
+            password_hash,
+            user_id,
+        )
+        .execute(pool)
+        .await;
 
         match result {
+            Ok(res) if res.rows_affected() > 0 => {
+                log::info!("Password hash updated for user {user_id}");
+                Ok(())
+            }
+            Ok(_) => {
+                log::error!(
+                    "User {user_id} disappeared during password update"
+                );
+                Err(UserError::NotFound)
+            }
             Err(e) => {
-                log::error!("Database error when deleting user {user_id}: {e}");
+                log::error!(
+                    "Database error when updating password of user {user_id}: {e}"
+                );
                 Err(UserError::Database(e))
             }
-            _ => {
-                log::info!("User {user_id} updated");
-                Ok(())
-            }
         }
     }
+
+    /// Sets a new password and revokes every refresh token except those of
+    /// `keep_session_id`, and every personal access token, in one
+    /// transaction. Returns the number of revoked refresh tokens.
+    pub async fn change_password(
+        pool: &PgPool,
+        user_id: i32,
+        password_hash: &str,
+        keep_session_id: Option<Uuid>,
+    ) -> Result<u64, UserError> {
+        let mut tx = pool.begin().await?;
+
+        let updated = sqlx::query!(
+            "UPDATE users SET password = $1 WHERE id = $2",
+            password_hash,
+            user_id,
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if updated == 0 {
+            log::error!("User {user_id} disappeared during password change");
+            return Err(UserError::NotFound);
+        }
+
+        let revoked = sqlx::query!(
+            r#"
+            DELETE FROM refresh_tokens
+            WHERE user_id = $1
+                AND ($2::uuid IS NULL OR family_id <> $2)
+            "#,
+            user_id,
+            keep_session_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        sqlx::query!(
+            r#"
+            UPDATE personal_access_tokens
+            SET revoked_at = NOW()
+            WHERE user_id = $1 AND revoked_at IS NULL
+            "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        log::info!(
+            "Password of user {user_id} changed, {revoked} refresh tokens revoked"
+        );
+        Ok(revoked)
+    }
+
+    /// Deletes the user together with their refresh tokens, which have no
+    /// foreign key to `users`. Personal access tokens, roles and the rest
+    /// go with the user through `ON DELETE CASCADE`.
+    pub async fn delete(pool: &PgPool, user_id: i32) -> Result<(), UserError> {
+        let mut tx = pool.begin().await?;
+
+        let revoked = sqlx::query!(
+            "DELETE FROM refresh_tokens WHERE user_id = $1",
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        let result = sqlx::query!("DELETE FROM users WHERE id = $1", user_id)
+            .execute(&mut *tx)
+            .await;
+
+        if let Err(e) = result {
+            log::error!("Database error when deleting user {user_id}: {e}");
+            return Err(UserError::Database(e));
+        }
+
+        tx.commit().await?;
+        log::info!("User {user_id} deleted, {revoked} refresh tokens revoked");
+        Ok(())
+    }
 }
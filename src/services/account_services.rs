@@ -0,0 +1,239 @@
+use actix_web::web::Data;
+use sqlx::PgPool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    config::app_config::AppConfig,
+    errors::{account_errors::AccountError, users_errors::UserError},
+    models::{
+        account_models::{
+            PasswordChangeRequest, PasswordResetConfirm, TokenPurpose,
+        },
+        users_models::User,
+    },
+    repositories::{
+        user_tokens_repository::UserTokensRepository,
+        users_repository::UserRepository,
+    },
+    services::{
+        denylist_services::TokenDenylist,
+        mailer_services::{Email, MailService, Mailer},
+        password_policy_services::PasswordPolicy,
+        password_services::{PasswordService, PasswordVerification},
+        token_hash_services::TokenHashService,
+    },
+};
+
+pub struct AccountService;
+
+impl AccountService {
+    /// Mails a reset link if the address belongs to a user. The outcome is
+    /// never reported to the caller, so the endpoint cannot be used to
+    /// find out which addresses are registered.
+    pub async fn request_password_reset(
+        pool: &PgPool,
+        config: &AppConfig,
+        mailer: Data<dyn Mailer>,
+        email: &str,
+    ) -> Result<(), AccountError> {
+        let user = match UserRepository::find_by_email(pool, email).await {
+            Ok(user) => user,
+            Err(UserError::NotFound) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let Some(email) = user.email else {
+            return Ok(());
+        };
+
+        let token = Self::issue_token(
+            pool,
+            config,
+            user.id,
+            TokenPurpose::PasswordReset,
+            &email,
+        )
+        .await?;
+        let body = format!(
+            "Someone asked to reset the password of '{}'.\n\n\
+             Open {}/password-reset?token={token}\n\
+             or send this token to POST /password-reset/confirm:\n\n\
+             {token}\n\n\
+             It expires in {} minutes. If you did not ask for this, \
+             ignore this mail.",
+            user.username,
+            config.account.public_url,
+            config.account.password_reset_ttl_secs / 60
+        );
+
+        let email = Email {
+            to: email,
+            subject: "Reset your password".to_string(),
+            body,
+            token: Some(token),
+        };
+        if let Err(e) = MailService::send(mailer, email).await {
+            log::error!("Failed to mail reset link to user {}: {e}", user.id);
+        }
+        Ok(())
+    }
+
+    /// Sets a new password with a reset token. The password must pass the
+    /// policy. Every session of the user ends: refresh tokens are deleted
+    /// and access tokens denylisted.
+    pub async fn reset_password(
+        pool: &PgPool,
+        config: &AppConfig,
+        policy: &PasswordPolicy,
+        denylist: &TokenDenylist,
+        request: &PasswordResetConfirm,
+    ) -> Result<(), AccountError> {
+        let token_hash = Self::hash_token(config, &request.token);
+        let username = UserTokensRepository::find_username(
+            pool,
+            &token_hash,
+            TokenPurpose::PasswordReset,
+        )
+        .await?
+        .ok_or(AccountError::InvalidToken)?;
+        policy.check(&username, &request.new_password).await?;
+
+        let password_hash =
+            PasswordService::hash(&config.password, &request.new_password)?;
+
+        let user_id = UserTokensRepository::reset_password(
+            pool,
+            &token_hash,
+            &password_hash,
+        )
+        .await?
+        .ok_or(AccountError::InvalidToken)?;
+        denylist.revoke_user(pool, user_id).await?;
+
+        Ok(())
+    }
+
+    /// Replaces the password of a signed-in user who knows the current
+    /// one. Every other session and every personal access token ends, and
+    /// all access tokens issued so far are revoked. The session of
+    /// `session_id` keeps its refresh token, so it stays signed in after
+    /// the next refresh. Returns the number of revoked refresh tokens.
+    pub async fn change_password(
+        pool: &PgPool,
+        config: &AppConfig,
+        policy: &PasswordPolicy,
+        denylist: &TokenDenylist,
+        user_id: i32,
+        session_id: Option<Uuid>,
+        request: &PasswordChangeRequest,
+    ) -> Result<u64, AccountError> {
+        let user = UserRepository::find_by_id(pool, user_id).await?;
+        let verification = PasswordService::verify(
+            &config.password,
+            &request.current_password,
+            &user.password,
+        )?;
+        if verification == PasswordVerification::Invalid {
+            return Err(AccountError::WrongPassword);
+        }
+        policy.check(&user.username, &request.new_password).await?;
+
+        let password_hash =
+            PasswordService::hash(&config.password, &request.new_password)?;
+        let revoked = UserRepository::change_password(
+            pool,
+            user_id,
+            &password_hash,
+            session_id,
+        )
+        .await?;
+        denylist.revoke_user(pool, user_id).await?;
+
+        Ok(revoked)
+    }
+
+    /// Mails a verification link to the current address of the user.
+    pub async fn send_email_verification(
+        pool: &PgPool,
+        config: &AppConfig,
+        mailer: Data<dyn Mailer>,
+        user: &User,
+    ) -> Result<(), AccountError> {
+        let Some(email) = user.email.clone() else {
+            return Err(AccountError::NoEmail);
+        };
+
+        let token = Self::issue_token(
+            pool,
+            config,
+            user.id,
+            TokenPurpose::EmailVerification,
+            &email,
+        )
+        .await?;
+        let body = format!(
+            "Confirm that {email} is the address of '{}'.\n\n\
+             Open {}/email-verification?token={token}\n\
+             or send this token to POST /email-verification/confirm:\n\n\
+             {token}\n\n\
+             It expires in {} hours.",
+            user.username,
+            config.account.public_url,
+            config.account.email_verification_ttl_secs / 3600
+        );
+
+        let email = Email {
+            to: email,
+            subject: "Verify your email address".to_string(),
+            body,
+            token: Some(token),
+        };
+        MailService::send(mailer, email).await?;
+        Ok(())
+    }
+
+    pub async fn verify_email(
+        pool: &PgPool,
+        config: &AppConfig,
+        token: &str,
+    ) -> Result<(), AccountError> {
+        let token_hash = Self::hash_token(config, token);
+
+        UserTokensRepository::verify_email(pool, &token_hash)
+            .await?
+            .ok_or(AccountError::InvalidToken)?;
+        Ok(())
+    }
+
+    async fn issue_token(
+        pool: &PgPool,
+        config: &AppConfig,
+        user_id: i32,
+        purpose: TokenPurpose,
+        email: &str,
+    ) -> Result<String, AccountError> {
+        let ttl = match purpose {
+            TokenPurpose::PasswordReset => config.account.password_reset_ttl(),
+            TokenPurpose::EmailVerification => {
+                config.account.email_verification_ttl()
+            }
+        };
+        let token = Uuid::new_v4().to_string();
+
+        UserTokensRepository::create(
+            pool,
+            &Self::hash_token(config, &token),
+            user_id,
+            purpose,
+            email,
+            OffsetDateTime::now_utc() + ttl,
+        )
+        .await?;
+
+        Ok(token)
+    }
+
+    fn hash_token(config: &AppConfig, token: &str) -> String {
+        TokenHashService::hash(&config.jwt.refresh_token_hmac_key, token)
+    }
+}
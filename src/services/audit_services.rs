@@ -0,0 +1,50 @@
+use sqlx::PgPool;
+
+use crate::{
+    errors::auth_errors::AuthError,
+    models::{
+        audit_models::{AuthEvent, AuthOutcome},
+        auth_models::ClientInfo,
+    },
+    repositories::audit_repository::AuditRepository,
+};
+
+pub struct AuditService;
+
+impl AuditService {
+    /// Records the event with the outcome of `result`. The audit trail must
+    /// not break logins, so a failed write is only logged.
+    pub async fn record<T>(
+        pool: &PgPool,
+        mut event: AuthEvent,
+        result: &Result<T, AuthError>,
+        client: &ClientInfo,
+    ) {
+        if let Err(e) = result {
+            event.outcome = AuthOutcome::Failure;
+            event.reason = Some(Self::reason(e));
+        }
+
+        if let Err(e) = AuditRepository::insert(pool, &event, client).await {
+            log::error!("Failed to record auth event {event:?}: {e}");
+        }
+    }
+
+    fn reason(error: &AuthError) -> &'static str {
+        match error {
+            AuthError::InvalidCredentials => "invalid_credentials",
+            AuthError::LoginLocked { .. } => "login_locked",
+            AuthError::TokenExpired => "token_expired",
+            AuthError::TokenRevoked => "token_revoked",
+            AuthError::InvalidToken(_) => "invalid_token",
+            AuthError::RefreshTokenNotFound => "refresh_token_not_found",
+            AuthError::RefreshTokenReused => "refresh_token_reused",
+            AuthError::MfaChallengeInvalid => "mfa_challenge_invalid",
+            AuthError::MfaInvalidCode => "mfa_invalid_code",
+            AuthError::InvalidScope(_) => "invalid_scope",
+            AuthError::InvalidAudience(_) => "invalid_audience",
+            AuthError::Validation(_) => "validation_failed",
+            _ => "internal_error",
+        }
+    }
+}
@@ -1,145 +1,810 @@
-use jsonwebtoken::{
-    DecodingKey, EncodingKey, Header, Validation, decode, encode,
-};
-use sqlx::PgPool;
 use crate::{
-    errors::{auth_errors::AuthError},
-    models::auth_models::{
-        Claims, LoginRequest, RefreshRequest, RefreshToken, TokenPair,
+    config::app_config::AppConfig,
+    errors::{auth_errors::AuthError, users_errors::UserError},
+    models::{
+        audit_models::{AuthEvent, AuthEventType},
+        auth_models::{
+            Claims, ClientInfo, LoginRequest, LoginResponse, RefreshRequest,
+            RefreshToken, RefreshTokenRecord, TokenGrant, TokenPair,
+        },
+        mfa_models::MfaLoginRequest,
     },
     repositories::{
-        auth_repisitory::AuthRepository, users_repository::UserRepository,
+        auth_repisitory::AuthRepository, mfa_repository::MfaRepository,
+        roles_repository::RolesRepository, users_repository::UserRepository,
+    },
+    services::{
+        audit_services::AuditService,
+        denylist_services::TokenDenylist,
+        key_ring_services::KeyRing,
+        login_throttle_services::LoginThrottleService,
+        mfa_services::MfaService,
+        password_services::{PasswordService, PasswordVerification},
+        personal_tokens_services::PersonalTokenService,
+        scope_services::ScopeService,
+        token_hash_services::TokenHashService,
+        user_id_cache_services::UserIdCache,
     },
 };
-
-const JWT_SECRET: &[u8] = b"your_secret_key";
+use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
 
 pub struct AuthService;
 
 impl AuthService {
     pub async fn login(
         pool: &PgPool,
+        config: &AppConfig,
+        key_ring: &KeyRing,
         credentials: LoginRequest,
-    ) -> Result<TokenPair, AuthError> {
-        let user_id = Self::authenticate_user(
+        client: &ClientInfo,
+    ) -> Result<LoginResponse, AuthError> {
+        let mut event = AuthEvent::new(AuthEventType::Login);
+        event.username = Some(credentials.username.clone());
+
+        let result = Self::attempt_login(
+            pool,
+            config,
+            key_ring,
+            credentials,
+            client,
+            &mut event,
+        )
+        .await;
+        AuditService::record(pool, event, &result, client).await;
+        result
+    }
+
+    async fn attempt_login(
+        pool: &PgPool,
+        config: &AppConfig,
+        key_ring: &KeyRing,
+        credentials: LoginRequest,
+        client: &ClientInfo,
+        event: &mut AuthEvent,
+    ) -> Result<LoginResponse, AuthError> {
+        let scope = credentials
+            .scope
+            .as_deref()
+            .map(|scope| ScopeService::narrow(scope, None))
+            .transpose()?;
+        let audience = Self::check_audience(config, credentials.audience)?;
+
+        let ip_address = client.ip_address.as_deref();
+        LoginThrottleService::check(pool, &credentials.username, ip_address)
+            .await?;
+
+        let user_id = match Self::authenticate_user(
             pool,
+            config,
             &credentials.username,
             &credentials.password,
         )
-        .await?;
+        .await
+        {
+            Ok(user_id) => user_id,
+            Err(e) => {
+                if matches!(e, AuthError::InvalidCredentials) {
+                    LoginThrottleService::record_failure(
+                        pool,
+                        &config.login_throttle,
+                        &credentials.username,
+                        ip_address,
+                    )
+                    .await?;
+                }
+                return Err(e);
+            }
+        };
+        event.user_id = Some(user_id);
+        LoginThrottleService::record_success(pool, &credentials.username)
+            .await?;
 
-        // Генерация токенов
-        let token_pair = Self::generate_token_pair(user_id)?;
-        let refresh_token = RefreshToken::new(user_id);
+        if MfaService::is_enabled(pool, user_id).await? {
+            event.reason = Some("mfa_required");
+            let challenge = MfaService::create_challenge(
+                pool,
+                config,
+                user_id,
+                scope.as_deref(),
+                audience.as_deref(),
+            )
+            .await?;
+            return Ok(LoginResponse::MfaRequired(challenge));
+        }
+
+        // Каждый вход начинает новое семейство refresh токенов
+        let grant =
+            TokenGrant { user_id, family_id: Uuid::new_v4(), scope, audience };
+        Self::issue_token_pair(pool, config, key_ring, &grant, None, client)
+            .await
+            .map(LoginResponse::Tokens)
+    }
+
+    /// Second step of a login with 2FA: redeems the `mfa_pending` token
+    /// from `/login` with a TOTP or recovery code. A token is dropped after
+    /// `mfa.max_attempts` wrong codes.
+    pub async fn login_mfa(
+        pool: &PgPool,
+        config: &AppConfig,
+        key_ring: &KeyRing,
+        request: MfaLoginRequest,
+        client: &ClientInfo,
+    ) -> Result<TokenPair, AuthError> {
+        let mut event = AuthEvent::new(AuthEventType::LoginMfa);
+        let result = Self::attempt_login_mfa(
+            pool, config, key_ring, request, client, &mut event,
+        )
+        .await;
+        AuditService::record(pool, event, &result, client).await;
+        result
+    }
+
+    async fn attempt_login_mfa(
+        pool: &PgPool,
+        config: &AppConfig,
+        key_ring: &KeyRing,
+        request: MfaLoginRequest,
+        client: &ClientInfo,
+        event: &mut AuthEvent,
+    ) -> Result<TokenPair, AuthError> {
+        let token_hash = TokenHashService::hash(
+            &config.jwt.refresh_token_hmac_key,
+            &request.mfa_token,
+        );
+        let Some(challenge) =
+            MfaRepository::find_challenge(pool, &token_hash).await?
+        else {
+            return Err(AuthError::MfaChallengeInvalid);
+        };
+        event.user_id = Some(challenge.user_id);
+        event.username = Some(challenge.username.clone());
+
+        // Коды перебираются так же, как пароли, поэтому блокировка общая
+        let ip_address = client.ip_address.as_deref();
+        LoginThrottleService::check(pool, &challenge.username, ip_address)
+            .await?;
+
+        if challenge.expires_at < OffsetDateTime::now_utc()
+            || challenge.attempts >= config.mfa.max_attempts
+        {
+            MfaRepository::delete_challenge(pool, &token_hash).await?;
+            return Err(AuthError::MfaChallengeInvalid);
+        }
+
+        let user_id = challenge.user_id;
+        if !MfaService::verify_code(pool, config, user_id, &request.code)
+            .await?
+        {
+            let attempts =
+                MfaRepository::record_challenge_failure(pool, &token_hash)
+                    .await?;
+            log::warn!("Wrong MFA code #{attempts} for user {user_id}");
+            if attempts >= config.mfa.max_attempts {
+                MfaRepository::delete_challenge(pool, &token_hash).await?;
+            }
+            LoginThrottleService::record_failure(
+                pool,
+                &config.login_throttle,
+                &challenge.username,
+                ip_address,
+            )
+            .await?;
+            return Err(AuthError::MfaInvalidCode);
+        }
+        LoginThrottleService::record_success(pool, &challenge.username)
+            .await?;
 
-        // Сохранение refresh token в БД
-        AuthRepository::save_refresh_token(pool, &refresh_token).await?;
+        if !MfaRepository::delete_challenge(pool, &token_hash).await? {
+            return Err(AuthError::MfaChallengeInvalid);
+        }
 
-        Ok(TokenPair {
-            access_token: token_pair.access_token,
-            refresh_token: refresh_token.token,
-        })
+        let grant = TokenGrant {
+            user_id,
+            family_id: Uuid::new_v4(),
+            scope: challenge.scope,
+            audience: challenge.audience,
+        };
+        Self::issue_token_pair(pool, config, key_ring, &grant, None, client)
+            .await
     }
 
     pub async fn refresh(
         pool: &PgPool,
+        config: &AppConfig,
+        key_ring: &KeyRing,
+        token_data: RefreshRequest,
+        client: &ClientInfo,
+    ) -> Result<TokenPair, AuthError> {
+        let mut event = AuthEvent::new(AuthEventType::Refresh);
+        let result = Self::rotate_refresh_token(
+            pool, config, key_ring, token_data, client, &mut event,
+        )
+        .await;
+        AuditService::record(pool, event, &result, client).await;
+        result
+    }
+
+    async fn rotate_refresh_token(
+        pool: &PgPool,
+        config: &AppConfig,
+        key_ring: &KeyRing,
         token_data: RefreshRequest,
+        client: &ClientInfo,
+        event: &mut AuthEvent,
     ) -> Result<TokenPair, AuthError> {
-        // Проверяем валидность refresh token
-        let user_id = AuthRepository::validate_refresh_token(
+        let token_hash = Self::hash_refresh_token(config, &token_data);
+        // Блокировка строки выстраивает параллельные refresh одного токена
+        // в очередь: следующий видит уже выданную замену
+        let mut tx = pool.begin().await?;
+        let record =
+            AuthRepository::lock_refresh_token(&mut tx, &token_hash).await?;
+        event.user_id = Some(record.user_id);
+        let grant = TokenGrant {
+            user_id: record.user_id,
+            family_id: record.family_id,
+            scope: record.scope.clone(),
+            audience: record.audience.clone(),
+        };
+
+        if let Some(consumed_at) = record.consumed_at {
+            let successor = RefreshToken::successor(
+                &grant,
+                config.jwt.refresh_token_ttl(),
+                &config.jwt.refresh_token_hmac_key,
+                &token_data.refresh_token,
+            );
+            // Замена тоже блокируется: если её уже сменили, повтор старого
+            // токена считается reuse
+            let successor_unused =
+                AuthRepository::lock_unconsumed(&mut tx, &successor.token_hash)
+                    .await?;
+            let reissue = record.successor_jti.filter(|_| {
+                Self::within_grace(
+                    consumed_at,
+                    OffsetDateTime::now_utc(),
+                    config.jwt.refresh_grace(),
+                    successor_unused,
+                )
+            });
+
+            let Some(successor_jti) = reissue else {
+                // Отзыв семейства удаляет заблокированные строки
+                tx.rollback().await?;
+                return Err(
+                    Self::handle_refresh_token_reuse(pool, &record).await
+                );
+            };
+            event.reason = Some("grace_period");
+            let result = Self::reissue_access_token(
+                pool,
+                config,
+                key_ring,
+                &token_data,
+                record,
+                successor_jti,
+                consumed_at,
+            )
+            .await
+            .map(|access_token| TokenPair {
+                access_token,
+                refresh_token: successor.token,
+            });
+            tx.rollback().await?;
+            return result;
+        }
+
+        if record.expires_at < OffsetDateTime::now_utc() {
+            log::warn!("Refresh token expired for user {}", record.user_id);
+            return Err(AuthError::TokenExpired);
+        }
+
+        // Проверяем scope до использования токена, чтобы неверный запрос
+        // не сжигал его
+        let access_scope = token_data
+            .scope
+            .as_deref()
+            .map(|scope| ScopeService::narrow(scope, record.scope.as_deref()))
+            .transpose()?;
+
+        let issued_at = OffsetDateTime::now_utc();
+        let jti = Uuid::new_v4();
+        let access_token = Self::generate_access_token(
             pool,
-            &token_data.refresh_token,
+            config,
+            key_ring,
+            &grant,
+            access_scope.or_else(|| grant.scope.clone()),
+            jti,
+            issued_at,
         )
         .await?;
+        let refresh_token = RefreshToken::successor(
+            &grant,
+            config.jwt.refresh_token_ttl(),
+            &config.jwt.refresh_token_hmac_key,
+            &token_data.refresh_token,
+        );
 
-        // Удаляем использованный refresh token
-        AuthRepository::delete_refresh_token(pool, &token_data.refresh_token)
+        // Помечаем refresh token использованным, но не удаляем его,
+        // чтобы распознать повторное предъявление
+        AuthRepository::save_refresh_token(&mut *tx, &refresh_token, client)
             .await?;
+        if !AuthRepository::consume_refresh_token(
+            &mut tx,
+            &token_hash,
+            issued_at,
+            jti,
+        )
+        .await?
+        {
+            tx.rollback().await?;
+            return Err(Self::handle_refresh_token_reuse(pool, &record).await);
+        }
+        tx.commit().await?;
+
+        Ok(TokenPair { access_token, refresh_token: refresh_token.token })
+    }
 
-        // Генерируем новую пару токенов
-        let token_pair = Self::generate_token_pair(user_id)?;
-        let new_refresh_token = RefreshToken::new(user_id);
+    /// The access token a token was rotated to, signed again for a
+    /// concurrent refresh within `jwt.refresh_grace_secs`. Only the roles
+    /// are read anew, so it is the same unless they changed.
+    async fn reissue_access_token(
+        pool: &PgPool,
+        config: &AppConfig,
+        key_ring: &KeyRing,
+        token_data: &RefreshRequest,
+        record: RefreshTokenRecord,
+        successor_jti: Uuid,
+        consumed_at: OffsetDateTime,
+    ) -> Result<String, AuthError> {
+        let access_scope = token_data
+            .scope
+            .as_deref()
+            .map(|scope| ScopeService::narrow(scope, record.scope.as_deref()))
+            .transpose()?;
+        let grant = TokenGrant {
+            user_id: record.user_id,
+            family_id: record.family_id,
+            scope: record.scope,
+            audience: record.audience,
+        };
 
-        // Сохраняем новый refresh token
-        AuthRepository::save_refresh_token(pool, &new_refresh_token).await?;
+        let access_token = Self::generate_access_token(
+            pool,
+            config,
+            key_ring,
+            &grant,
+            access_scope.or_else(|| grant.scope.clone()),
+            successor_jti,
+            consumed_at,
+        )
+        .await?;
+        log::info!(
+            "Refresh token of user {} presented again within the grace period",
+            grant.user_id
+        );
 
-        Ok(TokenPair {
-            access_token: token_pair.access_token,
-            refresh_token: new_refresh_token.token,
-        })
+        Ok(access_token)
     }
 
+    /// Whether a rotated token presented again gets the pair it was rotated
+    /// to: only within the grace period, and only while the new refresh
+    /// token has not been rotated itself.
+    fn within_grace(
+        consumed_at: OffsetDateTime,
+        now: OffsetDateTime,
+        grace: Duration,
+        successor_unused: bool,
+    ) -> bool {
+        successor_unused && now - consumed_at < grace
+    }
+
+    /// A consumed token was presented again, so either the client or an
+    /// attacker holds a stolen copy. The whole family is revoked, which
+    /// logs out both of them.
+    async fn handle_refresh_token_reuse(
+        pool: &PgPool,
+        record: &RefreshTokenRecord,
+    ) -> AuthError {
+        log::error!(
+            "Refresh token reuse detected for user {}, revoking family {}",
+            record.user_id,
+            record.family_id
+        );
+
+        if let Err(e) =
+            AuthRepository::revoke_refresh_token_family(pool, record.family_id)
+                .await
+        {
+            return e;
+        }
+        if let Err(e) = AuthRepository::record_refresh_token_reuse(
+            pool,
+            record.user_id,
+            record.family_id,
+        )
+        .await
+        {
+            return e;
+        }
+
+        AuthError::RefreshTokenReused
+    }
+
+    /// Revokes the refresh token family and, when an access token is sent
+    /// too, that token and its whole session.
     pub async fn logout(
         pool: &PgPool,
-        token_data: RefreshRequest,
+        config: &AppConfig,
+        key_ring: &KeyRing,
+        denylist: &TokenDenylist,
+        token_data: Option<RefreshRequest>,
+        access_token: Option<&str>,
+        client: &ClientInfo,
     ) -> Result<(), AuthError> {
-        AuthRepository::delete_refresh_token(pool, &token_data.refresh_token)
-            .await
+        let mut event = AuthEvent::new(AuthEventType::Logout);
+        let result = async {
+            if let Some(token_data) = token_data {
+                let token_hash = Self::hash_refresh_token(config, &token_data);
+                event.user_id = Some(
+                    AuthRepository::delete_refresh_token(pool, &token_hash)
+                        .await?,
+                );
+            }
+            if let Some(access_token) = access_token {
+                let user_id = Self::end_session(
+                    pool,
+                    config,
+                    key_ring,
+                    denylist,
+                    access_token,
+                )
+                .await?;
+                event.user_id = event.user_id.or(user_id);
+            }
+            Ok(())
+        }
+        .await;
+
+        AuditService::record(pool, event, &result, client).await;
+        result
+    }
+
+    fn hash_refresh_token(
+        config: &AppConfig,
+        token_data: &RefreshRequest,
+    ) -> String {
+        TokenHashService::hash(
+            &config.jwt.refresh_token_hmac_key,
+            &token_data.refresh_token,
+        )
     }
 
     pub async fn authenticate_user(
         pool: &PgPool,
+        config: &AppConfig,
         username: &str,
         password: &str,
     ) -> Result<i32, AuthError> {
         if username.is_empty() || password.is_empty() {
-            return Err(AuthError::Authentication(
-                "Username and password are required".to_string(),
-            ));
+            return Err(AuthError::InvalidCredentials);
         }
 
-        let user = UserRepository::find_by_username(pool, username)
-            .await
-            .map_err(|e| {
-                AuthError::Authentication(format!(
-                    "Authentication failed: {}",
-                    e
-                ))
-            })?;
-
-        if username == user.username && password == user.password {
-            Ok(user.id)
-        } else {
-            Err(AuthError::Authentication("Invalid credentials".to_string()))
+        // Неизвестный пользователь и неверный пароль неотличимы ни по
+        // ответу, ни по времени ответа
+        let user = match UserRepository::find_by_username(pool, username).await
+        {
+            Ok(user) => user,
+            Err(UserError::NotFound) => {
+                PasswordService::verify_dummy(&config.password, password);
+                log::debug!("Login attempt for unknown user '{username}'");
+                return Err(AuthError::InvalidCredentials);
+            }
+            Err(UserError::Database(e)) => return Err(AuthError::Database(e)),
+            Err(e) => {
+                return Err(AuthError::Authentication(format!(
+                    "Authentication failed: {e}"
+                )));
+            }
+        };
+
+        match PasswordService::verify(
+            &config.password,
+            password,
+            &user.password,
+        )? {
+            PasswordVerification::Valid { needs_rehash } => {
+                if needs_rehash {
+                    Self::rehash_password(pool, config, user.id, password)
+                        .await;
+                }
+                Ok(user.id)
+            }
+            PasswordVerification::Invalid => {
+                log::debug!("Wrong password for user {}", user.id);
+                Err(AuthError::InvalidCredentials)
+            }
         }
     }
 
-    pub fn generate_token_pair(user_id: i32) -> Result<TokenPair, AuthError> {
-        let claims = Claims::new(user_id);
-        let refresh_token = RefreshToken::new(user_id);
+    /// Upgrades a legacy or outdated password hash after a successful login.
+    /// Failures are logged only, the login itself has already succeeded.
+    async fn rehash_password(
+        pool: &PgPool,
+        config: &AppConfig,
+        user_id: i32,
+        password: &str,
+    ) {
+        let password_hash =
+            match PasswordService::hash(&config.password, password) {
+                Ok(hash) => hash,
+                Err(e) => {
+                    log::error!(
+                        "Failed to rehash password for user {user_id}: {e}"
+                    );
+                    return;
+                }
+            };
+
+        if let Err(e) =
+            UserRepository::update_password_hash(pool, user_id, &password_hash)
+                .await
+        {
+            log::error!(
+                "Failed to store rehashed password for user {user_id}: {e}"
+            );
+        }
+    }
 
-        let access_token = encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(JWT_SECRET),
+    /// Issues an access token and a refresh token of the granted family.
+    /// The access token gets `access_scope` if given, otherwise the scope
+    /// of the grant.
+    async fn issue_token_pair(
+        pool: &PgPool,
+        config: &AppConfig,
+        key_ring: &KeyRing,
+        grant: &TokenGrant,
+        access_scope: Option<String>,
+        client: &ClientInfo,
+    ) -> Result<TokenPair, AuthError> {
+        let access_token = Self::generate_access_token(
+            pool,
+            config,
+            key_ring,
+            grant,
+            access_scope.or_else(|| grant.scope.clone()),
+            Uuid::new_v4(),
+            OffsetDateTime::now_utc(),
         )
-        .map_err(AuthError::InvalidToken)?;
+        .await?;
+        let refresh_token = RefreshToken::new(
+            grant,
+            config.jwt.refresh_token_ttl(),
+            &config.jwt.refresh_token_hmac_key,
+        );
+
+        AuthRepository::save_refresh_token(pool, &refresh_token, client)
+            .await?;
 
         Ok(TokenPair { access_token, refresh_token: refresh_token.token })
     }
 
-    pub fn validate_access_token(token: &str) -> Result<Claims, AuthError> {
-        decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(JWT_SECRET),
-            &Validation::default(),
-        )
-        .map(|token_data| {
-            log::debug!(
-                "Access token validated for user {}",
-                token_data.claims.sub
-            );
-            token_data.claims
-        })
-        .map_err(|e| match e.kind() {
-            jsonwebtoken::errors::ErrorKind::ExpiredSignature => {
-                log::warn!("Access token expired");
-                AuthError::TokenExpired
-            }
-            _ => {
-                log::warn!("Invalid access token: {}", e);
-                AuthError::InvalidToken(e)
+    /// The audience asked for at login, which must be configured. `None`
+    /// stands for the default audience.
+    fn check_audience(
+        config: &AppConfig,
+        requested: Option<String>,
+    ) -> Result<Option<String>, AuthError> {
+        match requested {
+            Some(audience) if !config.jwt.audiences.contains(&audience) => {
+                Err(AuthError::InvalidAudience(audience))
             }
-        })
+            requested => Ok(requested),
+        }
+    }
+
+    /// Signs an access token of the grant's session. The same `jti` and
+    /// `issued_at` give the same token as long as the roles are unchanged.
+    async fn generate_access_token(
+        pool: &PgPool,
+        config: &AppConfig,
+        key_ring: &KeyRing,
+        grant: &TokenGrant,
+        scope: Option<String>,
+        jti: Uuid,
+        issued_at: OffsetDateTime,
+    ) -> Result<String, AuthError> {
+        // Роли читаются при каждой выдаче, изменения вступают в силу
+        // со следующим refresh
+        let roles =
+            RolesRepository::find_user_roles(pool, grant.user_id).await?;
+        let public_id =
+            match UserRepository::find_public_id(pool, grant.user_id).await {
+                Ok(public_id) => public_id,
+                Err(UserError::Database(e)) => {
+                    return Err(AuthError::Database(e));
+                }
+                Err(e) => return Err(AuthError::Authentication(e.to_string())),
+            };
+
+        let claims = Claims::new(
+            &config.jwt,
+            grant,
+            public_id,
+            roles,
+            scope,
+            jti,
+            issued_at,
+        );
+        key_ring.encode(&claims).map_err(AuthError::InvalidToken)
+    }
+
+    /// Revokes the access token presented on logout and ends its session,
+    /// so the refresh token family stops working too. Returns the user of
+    /// the token; invalid or expired tokens are ignored.
+    async fn end_session(
+        pool: &PgPool,
+        config: &AppConfig,
+        key_ring: &KeyRing,
+        denylist: &TokenDenylist,
+        token: &str,
+    ) -> Result<Option<i32>, AuthError> {
+        let mut claims =
+            match Self::decode_access_token(config, key_ring, token) {
+                Ok(claims) => claims,
+                Err(e) => {
+                    log::debug!("Access token not revoked on logout: {e}");
+                    return Ok(None);
+                }
+            };
+        match UserRepository::find_id(pool, claims.sub).await {
+            Ok(Some(user_id)) => claims.user_id = user_id,
+            Ok(None) => return Ok(None),
+            Err(UserError::Database(e)) => return Err(AuthError::Database(e)),
+            Err(e) => return Err(AuthError::Authentication(e.to_string())),
+        }
+
+        denylist.revoke_token(pool, &claims).await?;
+        if let Some(session_id) = claims.sid {
+            AuthRepository::revoke_refresh_token_family(pool, session_id)
+                .await?;
+        }
+        Ok(Some(claims.user_id))
+    }
+
+    pub async fn validate_access_token(
+        pool: &PgPool,
+        config: &AppConfig,
+        key_ring: &KeyRing,
+        denylist: &TokenDenylist,
+        user_ids: &UserIdCache,
+        token: &str,
+    ) -> Result<Claims, AuthError> {
+        let mut claims = Self::decode_access_token(config, key_ring, token)?;
+        claims.user_id = user_ids.resolve(pool, claims.sub).await?;
+
+        if denylist.is_revoked(&claims) {
+            log::warn!(
+                "Revoked access token {} used by user {}",
+                claims.jti,
+                claims.user_id
+            );
+            return Err(AuthError::TokenRevoked);
+        }
+
+        log::debug!("Access token validated for user {}", claims.user_id);
+        Ok(claims)
+    }
+
+    /// Claims of a bearer credential: a personal access token, recognised
+    /// by its prefix, or else an access token.
+    pub async fn validate_bearer_token(
+        pool: &PgPool,
+        config: &AppConfig,
+        key_ring: &KeyRing,
+        denylist: &TokenDenylist,
+        user_ids: &UserIdCache,
+        token: &str,
+    ) -> Result<Claims, AuthError> {
+        if PersonalTokenService::is_personal_token(token) {
+            PersonalTokenService::authenticate(pool, config, token).await
+        } else {
+            Self::validate_access_token(
+                pool, config, key_ring, denylist, user_ids, token,
+            )
+            .await
+        }
+    }
+
+    /// Checks the signature, `exp`, `nbf`, `iss` and `aud` of a token.
+    fn decode_access_token(
+        config: &AppConfig,
+        key_ring: &KeyRing,
+        token: &str,
+    ) -> Result<Claims, AuthError> {
+        key_ring
+            .decode::<Claims>(token, config.jwt.validation())
+            .map(|token_data| token_data.claims)
+            .map_err(|e| {
+                if let jsonwebtoken::errors::ErrorKind::ExpiredSignature =
+                    e.kind()
+                {
+                    log::warn!("Access token expired");
+                    AuthError::TokenExpired
+                } else {
+                    log::warn!("Invalid access token: {e}");
+                    AuthError::InvalidToken(e)
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "test-refresh-hmac-key";
+
+    fn grant() -> TokenGrant {
+        TokenGrant {
+            user_id: 1,
+            family_id: Uuid::new_v4(),
+            scope: None,
+            audience: None,
+        }
+    }
+
+    #[test]
+    fn reissues_within_grace_while_successor_unused() {
+        let now = OffsetDateTime::now_utc();
+        let grace = Duration::seconds(10);
+
+        assert!(AuthService::within_grace(
+            now - Duration::seconds(3),
+            now,
+            grace,
+            true
+        ));
+    }
+
+    #[test]
+    fn rejects_replay_after_grace() {
+        let now = OffsetDateTime::now_utc();
+        let grace = Duration::seconds(10);
+
+        assert!(!AuthService::within_grace(
+            now - Duration::seconds(10),
+            now,
+            grace,
+            true
+        ));
+        assert!(!AuthService::within_grace(
+            now - Duration::seconds(1),
+            now,
+            Duration::ZERO,
+            true
+        ));
+    }
+
+    #[test]
+    fn rejects_replay_once_successor_rotated() {
+        let grant = grant();
+        let ttl = Duration::days(1);
+        let first = RefreshToken::successor(&grant, ttl, KEY, "original");
+        let second = RefreshToken::successor(&grant, ttl, KEY, &first.token);
+        let now = OffsetDateTime::now_utc();
+
+        // Повтор выдаёт ту же замену, а у замены своя, другая
+        let replayed = RefreshToken::successor(&grant, ttl, KEY, "original");
+        assert_eq!(replayed.token_hash, first.token_hash);
+        assert_ne!(second.token_hash, first.token_hash);
+        assert!(!AuthService::within_grace(
+            now - Duration::seconds(1),
+            now,
+            Duration::seconds(10),
+            false
+        ));
     }
 }
@@ -0,0 +1,270 @@
+use actix_web::{
+    HttpRequest,
+    cookie::{Cookie, SameSite, time::Duration},
+    http::Method,
+};
+use rand::RngCore;
+use subtle::ConstantTimeEq;
+
+use crate::{
+    config::app_config::{AppConfig, CookieConfig, CookieSameSite},
+    errors::auth_errors::AuthError,
+    models::auth_models::TokenPair,
+};
+
+pub const REFRESH_COOKIE: &str = "refresh_token";
+pub const ACCESS_COOKIE: &str = "access_token";
+pub const CSRF_COOKIE: &str = "csrf_token";
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+/// The refresh token is only sent to `/refresh` and below.
+const REFRESH_COOKIE_PATH: &str = "/refresh";
+
+pub struct CookieService;
+
+impl CookieService {
+    /// Cookies for a newly issued token pair. The CSRF cookie is readable
+    /// by scripts, so the client can echo it in [`CSRF_HEADER`].
+    pub fn token_cookies(
+        config: &AppConfig,
+        token_pair: &TokenPair,
+        csrf_token: &str,
+    ) -> Vec<Cookie<'static>> {
+        let cookies = &config.cookies;
+        let refresh_ttl = Duration::seconds(config.jwt.refresh_token_ttl_secs);
+
+        let mut csrf_cookie = Self::build(
+            cookies,
+            CSRF_COOKIE,
+            csrf_token.to_string(),
+            "/",
+            refresh_ttl,
+        );
+        csrf_cookie.set_http_only(false);
+
+        let mut result = vec![
+            Self::build(
+                cookies,
+                REFRESH_COOKIE,
+                token_pair.refresh_token.clone(),
+                REFRESH_COOKIE_PATH,
+                refresh_ttl,
+            ),
+            csrf_cookie,
+        ];
+        if cookies.access_token {
+            result.push(Self::build(
+                cookies,
+                ACCESS_COOKIE,
+                token_pair.access_token.clone(),
+                "/",
+                Duration::seconds(config.jwt.access_token_ttl_secs),
+            ));
+        }
+
+        result
+    }
+
+    /// Expired copies of every auth cookie, sent on logout.
+    pub fn removal_cookies(config: &CookieConfig) -> Vec<Cookie<'static>> {
+        [
+            (REFRESH_COOKIE, REFRESH_COOKIE_PATH),
+            (CSRF_COOKIE, "/"),
+            (ACCESS_COOKIE, "/"),
+        ]
+        .into_iter()
+        .map(|(name, path)| {
+            Self::build(config, name, String::new(), path, Duration::ZERO)
+        })
+        .collect()
+    }
+
+    pub fn generate_csrf_token() -> String {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        hex::encode(bytes)
+    }
+
+    pub fn refresh_token(req: &HttpRequest) -> Option<String> {
+        req.cookie(REFRESH_COOKIE).map(|cookie| cookie.value().to_string())
+    }
+
+    /// The access token cookie, if cookie mode sets one. State-changing
+    /// requests must carry the CSRF token as well.
+    pub fn access_token(
+        req: &HttpRequest,
+        config: &CookieConfig,
+    ) -> Result<Option<String>, AuthError> {
+        if !config.enabled || !config.access_token {
+            return Ok(None);
+        }
+        let Some(cookie) = req.cookie(ACCESS_COOKIE) else {
+            return Ok(None);
+        };
+
+        if !Self::is_safe_method(req.method()) {
+            Self::verify_csrf(req)?;
+        }
+        Ok(Some(cookie.value().to_string()))
+    }
+
+    /// Double-submit check: the header must equal the CSRF cookie, which a
+    /// cross-site page can neither read nor set.
+    pub fn verify_csrf(req: &HttpRequest) -> Result<(), AuthError> {
+        let cookie = req.cookie(CSRF_COOKIE);
+        let header = req
+            .headers()
+            .get(CSRF_HEADER)
+            .and_then(|value| value.to_str().ok());
+
+        match (cookie, header) {
+            (Some(cookie), Some(header))
+                if !header.is_empty()
+                    && bool::from(
+                        cookie.value().as_bytes().ct_eq(header.as_bytes()),
+                    ) =>
+            {
+                Ok(())
+            }
+            _ => Err(AuthError::CsrfTokenInvalid),
+        }
+    }
+
+    fn is_safe_method(method: &Method) -> bool {
+        matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+    }
+
+    fn build(
+        config: &CookieConfig,
+        name: &'static str,
+        value: String,
+        path: &'static str,
+        max_age: Duration,
+    ) -> Cookie<'static> {
+        let mut cookie = Cookie::build(name, value)
+            .path(path)
+            .secure(config.secure)
+            .http_only(true)
+            .same_site(match config.same_site {
+                CookieSameSite::Strict => SameSite::Strict,
+                CookieSameSite::Lax => SameSite::Lax,
+                CookieSameSite::None => SameSite::None,
+            })
+            .max_age(max_age)
+            .finish();
+        if let Some(domain) = &config.domain {
+            cookie.set_domain(domain.clone());
+        }
+
+        cookie
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    const CSRF_TOKEN: &str = "csrf-token";
+
+    fn cookie_config() -> CookieConfig {
+        CookieConfig { enabled: true, access_token: true, ..Default::default() }
+    }
+
+    fn with_cookies(method: Method) -> TestRequest {
+        TestRequest::default()
+            .method(method)
+            .cookie(Cookie::new(ACCESS_COOKIE, "access"))
+            .cookie(Cookie::new(CSRF_COOKIE, CSRF_TOKEN))
+    }
+
+    fn access_token(request: TestRequest) -> Result<Option<String>, AuthError> {
+        CookieService::access_token(
+            &request.to_http_request(),
+            &cookie_config(),
+        )
+    }
+
+    #[test]
+    fn csrf_header_must_match_cookie() {
+        let matching = with_cookies(Method::POST)
+            .insert_header((CSRF_HEADER, CSRF_TOKEN))
+            .to_http_request();
+        let mismatched = with_cookies(Method::POST)
+            .insert_header((CSRF_HEADER, "other-token"))
+            .to_http_request();
+        let empty = TestRequest::default()
+            .cookie(Cookie::new(CSRF_COOKIE, ""))
+            .insert_header((CSRF_HEADER, ""))
+            .to_http_request();
+
+        assert!(CookieService::verify_csrf(&matching).is_ok());
+        assert!(matches!(
+            CookieService::verify_csrf(&mismatched),
+            Err(AuthError::CsrfTokenInvalid)
+        ));
+        assert!(matches!(
+            CookieService::verify_csrf(&empty),
+            Err(AuthError::CsrfTokenInvalid)
+        ));
+    }
+
+    #[test]
+    fn csrf_header_is_required() {
+        let request = with_cookies(Method::POST).to_http_request();
+        let without_cookie = TestRequest::default()
+            .insert_header((CSRF_HEADER, CSRF_TOKEN))
+            .to_http_request();
+
+        assert!(matches!(
+            CookieService::verify_csrf(&request),
+            Err(AuthError::CsrfTokenInvalid)
+        ));
+        assert!(matches!(
+            CookieService::verify_csrf(&without_cookie),
+            Err(AuthError::CsrfTokenInvalid)
+        ));
+    }
+
+    #[test]
+    fn access_cookie_needs_csrf_on_unsafe_methods() {
+        for method in [Method::POST, Method::PUT, Method::DELETE] {
+            assert!(matches!(
+                access_token(with_cookies(method.clone())),
+                Err(AuthError::CsrfTokenInvalid)
+            ));
+            assert_eq!(
+                access_token(
+                    with_cookies(method)
+                        .insert_header((CSRF_HEADER, CSRF_TOKEN))
+                )
+                .unwrap()
+                .as_deref(),
+                Some("access")
+            );
+        }
+    }
+
+    #[test]
+    fn safe_methods_skip_csrf_check() {
+        for method in [Method::GET, Method::HEAD, Method::OPTIONS] {
+            assert_eq!(
+                access_token(with_cookies(method)).unwrap().as_deref(),
+                Some("access")
+            );
+        }
+    }
+
+    #[test]
+    fn access_cookie_ignored_unless_enabled() {
+        let request = with_cookies(Method::POST).to_http_request();
+        let disabled = CookieConfig { access_token: false, ..cookie_config() };
+
+        assert_eq!(
+            CookieService::access_token(&request, &disabled).unwrap(),
+            None
+        );
+        assert_eq!(access_token(TestRequest::default()).unwrap(), None);
+    }
+}
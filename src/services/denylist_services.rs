@@ -0,0 +1,234 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{PoisonError, RwLock},
+};
+
+use actix_web::{rt, web::Data};
+use sqlx::{Error as SqlxError, PgPool};
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::{
+    config::app_config::JwtConfig, models::auth_models::Claims,
+    repositories::denylist_repository::DenylistRepository,
+};
+
+#[derive(Default)]
+struct Snapshot {
+    jtis: HashSet<Uuid>,
+    /// Cutoff per user in microseconds since the Unix epoch, compared
+    /// with [`Claims::issued_at_us`].
+    not_before: HashMap<i32, i64>,
+}
+
+/// In-process copy of the access token denylist. Checking a token never
+/// hits the database: revocations made by this instance apply at once,
+/// those made by other instances once the cache is next refreshed.
+pub struct TokenDenylist {
+    snapshot: RwLock<Snapshot>,
+    access_token_ttl: Duration,
+    leeway: Duration,
+    refresh_interval: std::time::Duration,
+}
+
+impl TokenDenylist {
+    pub fn new(config: &JwtConfig) -> Self {
+        TokenDenylist {
+            snapshot: RwLock::new(Snapshot::default()),
+            access_token_ttl: config.access_token_ttl(),
+            leeway: config.leeway(),
+            refresh_interval: std::time::Duration::from_secs(
+                config.denylist_refresh_secs,
+            ),
+        }
+    }
+
+    pub fn is_revoked(&self, claims: &Claims) -> bool {
+        let snapshot =
+            self.snapshot.read().unwrap_or_else(PoisonError::into_inner);
+
+        snapshot.jtis.contains(&claims.jti)
+            || snapshot
+                .not_before
+                .get(&claims.user_id)
+                .is_some_and(|&not_before| claims.issued_at_us() < not_before)
+    }
+
+    /// Reloads the cache from the database.
+    pub async fn refresh(&self, pool: &PgPool) -> Result<(), SqlxError> {
+        // Просроченный токен принимается ещё `leeway_secs`
+        let expires_after = OffsetDateTime::now_utc() - self.leeway;
+        let jtis =
+            DenylistRepository::find_revoked_jtis(pool, expires_after).await?;
+        let since = expires_after - self.access_token_ttl;
+        let cutoffs = DenylistRepository::find_cutoffs(pool, since).await?;
+
+        let snapshot = Snapshot {
+            jtis: jtis.into_iter().collect(),
+            not_before: cutoffs
+                .into_iter()
+                .map(|(user_id, not_before)| (user_id, unix_us(not_before)))
+                .collect(),
+        };
+        log::debug!(
+            "Denylist refreshed: {} tokens, {} users",
+            snapshot.jtis.len(),
+            snapshot.not_before.len()
+        );
+
+        *self.snapshot.write().unwrap_or_else(PoisonError::into_inner) =
+            snapshot;
+        Ok(())
+    }
+
+    /// Refreshes the cache every `jwt.denylist_refresh_secs` in the
+    /// background.
+    pub fn spawn_refresh(denylist: Data<Self>, pool: PgPool) {
+        rt::spawn(async move {
+            let mut interval = rt::time::interval(denylist.refresh_interval);
+            // Первый тик срабатывает сразу, а кэш уже загружен при старте
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+                if let Err(e) = denylist.refresh(&pool).await {
+                    log::error!("Failed to refresh token denylist: {e}");
+                }
+            }
+        });
+    }
+
+    pub async fn revoke_token(
+        &self,
+        pool: &PgPool,
+        claims: &Claims,
+    ) -> Result<(), SqlxError> {
+        DenylistRepository::revoke_jti(
+            pool,
+            claims.jti,
+            claims.user_id,
+            claims.exp,
+        )
+        .await?;
+
+        self.snapshot
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .jtis
+            .insert(claims.jti);
+        Ok(())
+    }
+
+    /// Revokes every access token issued to the user up to now.
+    pub async fn revoke_user(
+        &self,
+        pool: &PgPool,
+        user_id: i32,
+    ) -> Result<(), SqlxError> {
+        // Отсечка берётся по часам приложения, как и `iat` токенов
+        let not_before = OffsetDateTime::now_utc();
+        DenylistRepository::revoke_user_tokens(pool, user_id, not_before)
+            .await?;
+
+        self.snapshot
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .not_before
+            .insert(user_id, unix_us(not_before));
+        Ok(())
+    }
+}
+
+fn unix_us(time: OffsetDateTime) -> i64 {
+    i64::try_from(time.unix_timestamp_nanos() / 1000).unwrap_or(i64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::auth_models::TokenGrant;
+
+    use super::*;
+
+    const USER_ID: i32 = 7;
+
+    fn claims_at(issued_at: OffsetDateTime) -> Claims {
+        let grant = TokenGrant {
+            user_id: USER_ID,
+            family_id: Uuid::new_v4(),
+            scope: None,
+            audience: None,
+        };
+        Claims::new(
+            &JwtConfig::default(),
+            &grant,
+            Uuid::new_v4(),
+            Vec::new(),
+            None,
+            Uuid::new_v4(),
+            issued_at,
+        )
+    }
+
+    fn denylist_with_cutoff(not_before: OffsetDateTime) -> TokenDenylist {
+        let denylist = TokenDenylist::new(&JwtConfig::default());
+        denylist
+            .snapshot
+            .write()
+            .unwrap()
+            .not_before
+            .insert(USER_ID, unix_us(not_before));
+        denylist
+    }
+
+    #[test]
+    fn cutoff_is_compared_below_the_second() {
+        let cutoff = OffsetDateTime::from_unix_timestamp(1_700_000_000)
+            .unwrap()
+            + Duration::milliseconds(500);
+        let denylist = denylist_with_cutoff(cutoff);
+
+        let before = claims_at(cutoff - Duration::milliseconds(200));
+        let after = claims_at(cutoff + Duration::milliseconds(200));
+
+        assert_eq!(before.iat, after.iat);
+        assert!(denylist.is_revoked(&before));
+        assert!(!denylist.is_revoked(&after));
+    }
+
+    #[test]
+    fn token_issued_at_the_cutoff_stays_valid() {
+        let cutoff = OffsetDateTime::from_unix_timestamp(1_700_000_000)
+            .unwrap()
+            + Duration::microseconds(250);
+        let denylist = denylist_with_cutoff(cutoff);
+
+        assert!(!denylist.is_revoked(&claims_at(cutoff)));
+        assert!(
+            denylist.is_revoked(&claims_at(cutoff - Duration::microseconds(1)))
+        );
+    }
+
+    #[test]
+    fn tokens_without_microseconds_count_from_the_start_of_iat() {
+        let cutoff = OffsetDateTime::from_unix_timestamp(1_700_000_000)
+            .unwrap()
+            + Duration::milliseconds(500);
+        let denylist = denylist_with_cutoff(cutoff);
+
+        let mut legacy = claims_at(cutoff + Duration::milliseconds(200));
+        legacy.iat_us = 0;
+
+        assert!(denylist.is_revoked(&legacy));
+    }
+
+    #[test]
+    fn other_users_are_not_affected() {
+        let now = OffsetDateTime::now_utc();
+        let denylist = denylist_with_cutoff(now);
+
+        let mut claims = claims_at(now - Duration::seconds(1));
+        claims.user_id = USER_ID + 1;
+
+        assert!(!denylist.is_revoked(&claims));
+    }
+}
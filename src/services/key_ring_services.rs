@@ -0,0 +1,392 @@
+use std::{collections::HashMap, fs, str::FromStr};
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use ed25519_dalek::{VerifyingKey, pkcs8::DecodePublicKey};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, TokenData, Validation, decode,
+    decode_header, encode,
+    errors::{Error as JwtError, ErrorKind},
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet,
+        KeyAlgorithm, OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse,
+        RSAKeyParameters, RSAKeyType,
+    },
+};
+use rsa::{RsaPublicKey, pkcs1::DecodeRsaPublicKey, traits::PublicKeyParts};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    config::app_config::{JwtConfig, JwtKeyConfig},
+    errors::config_errors::ConfigError,
+};
+
+struct VerificationKey {
+    algorithm: Algorithm,
+    decoding_key: DecodingKey,
+}
+
+/// Keys used to sign and verify access tokens. Exactly one key signs, every
+/// key in the ring verifies, which lets retired keys keep validating the
+/// tokens they issued until those expire.
+pub struct KeyRing {
+    signing_kid: String,
+    signing_algorithm: Algorithm,
+    encoding_key: EncodingKey,
+    verification_keys: HashMap<String, VerificationKey>,
+    jwks: JwkSet,
+}
+
+impl KeyRing {
+    pub fn from_config(config: &JwtConfig) -> Result<Self, ConfigError> {
+        if config.keys.is_empty() {
+            return Ok(Self::from_secret(&config.signing_kid, &config.secret));
+        }
+
+        let mut verification_keys = HashMap::new();
+        let mut jwks = JwkSet { keys: Vec::new() };
+        let mut encoding_key = None;
+
+        for key in &config.keys {
+            let public_pem = read_key(&key.public_key_path)?;
+            let jwk = public_jwk(key, &public_pem)?;
+            let decoding_key = DecodingKey::from_jwk(&jwk)
+                .map_err(|e| invalid_key(&key.kid, &e.to_string()))?;
+
+            if key.kid == config.signing_kid {
+                encoding_key = Some(signing_key(key)?);
+            }
+
+            verification_keys.insert(
+                key.kid.clone(),
+                VerificationKey { algorithm: key.algorithm, decoding_key },
+            );
+            jwks.keys.push(jwk);
+        }
+
+        let signing_algorithm = verification_keys
+            .get(&config.signing_kid)
+            .map(|key| key.algorithm)
+            .ok_or_else(|| invalid_key(&config.signing_kid, "not found"))?;
+        let encoding_key = encoding_key.ok_or_else(|| {
+            invalid_key(&config.signing_kid, "no private key")
+        })?;
+
+        log::info!(
+            "Loaded {} JWT verification keys, signing with '{}'",
+            verification_keys.len(),
+            config.signing_kid
+        );
+
+        Ok(KeyRing {
+            signing_kid: config.signing_kid.clone(),
+            signing_algorithm,
+            encoding_key,
+            verification_keys,
+            jwks,
+        })
+    }
+
+    /// Shared-secret HS256 ring. Symmetric keys are never published, so its
+    /// JWKS is empty.
+    fn from_secret(kid: &str, secret: &str) -> Self {
+        let verification_key = VerificationKey {
+            algorithm: Algorithm::HS256,
+            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+        };
+
+        KeyRing {
+            signing_kid: kid.to_string(),
+            signing_algorithm: Algorithm::HS256,
+            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
+            verification_keys: HashMap::from([(
+                kid.to_string(),
+                verification_key,
+            )]),
+            jwks: JwkSet { keys: Vec::new() },
+        }
+    }
+
+    pub fn encode<T: Serialize>(&self, claims: &T) -> Result<String, JwtError> {
+        let mut header = Header::new(self.signing_algorithm);
+        header.kid = Some(self.signing_kid.clone());
+
+        encode(&header, claims, &self.encoding_key)
+    }
+
+    /// Verifies a token with the key named by its `kid` header. Tokens
+    /// without a `kid` predate key rotation and are checked against the
+    /// current signing key.
+    pub fn decode<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        mut validation: Validation,
+    ) -> Result<TokenData<T>, JwtError> {
+        let header = decode_header(token)?;
+        let kid = header.kid.as_deref().unwrap_or(&self.signing_kid);
+
+        let Some(key) = self.verification_keys.get(kid) else {
+            log::warn!("Access token signed with unknown key '{kid}'");
+            return Err(ErrorKind::InvalidToken.into());
+        };
+
+        validation.algorithms = vec![key.algorithm];
+        decode(token, &key.decoding_key, &validation)
+    }
+
+    pub fn jwks(&self) -> &JwkSet {
+        &self.jwks
+    }
+}
+
+fn read_key(path: &str) -> Result<String, ConfigError> {
+    fs::read_to_string(path)
+        .map_err(|source| ConfigError::Read { path: path.to_string(), source })
+}
+
+fn signing_key(key: &JwtKeyConfig) -> Result<EncodingKey, ConfigError> {
+    let Some(path) = &key.private_key_path else {
+        return Err(invalid_key(&key.kid, "no private key"));
+    };
+    let pem = read_key(path)?;
+
+    let encoding_key = if key.algorithm == Algorithm::EdDSA {
+        EncodingKey::from_ed_pem(pem.as_bytes())
+    } else {
+        EncodingKey::from_rsa_pem(pem.as_bytes())
+    };
+
+    encoding_key.map_err(|e| invalid_key(&key.kid, &e.to_string()))
+}
+
+/// Builds the public JWK for a key from its PEM encoded public half.
+fn public_jwk(key: &JwtKeyConfig, pem: &str) -> Result<Jwk, ConfigError> {
+    let algorithm = if key.algorithm == Algorithm::EdDSA {
+        let public_key = VerifyingKey::from_public_key_pem(pem)
+            .map_err(|e| invalid_key(&key.kid, &e.to_string()))?;
+
+        AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+            key_type: OctetKeyPairType::OctetKeyPair,
+            curve: EllipticCurve::Ed25519,
+            x: URL_SAFE_NO_PAD.encode(public_key.as_bytes()),
+        })
+    } else {
+        let public_key = RsaPublicKey::from_public_key_pem(pem)
+            .or_else(|_| RsaPublicKey::from_pkcs1_pem(pem))
+            .map_err(|e| invalid_key(&key.kid, &e.to_string()))?;
+
+        AlgorithmParameters::RSA(RSAKeyParameters {
+            key_type: RSAKeyType::RSA,
+            n: URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be()),
+            e: URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be()),
+        })
+    };
+
+    let key_algorithm = KeyAlgorithm::from_str(&format!("{:?}", key.algorithm))
+        .map_err(|e| invalid_key(&key.kid, &e.to_string()))?;
+
+    Ok(Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm),
+            key_id: Some(key.kid.clone()),
+            ..CommonParameters::default()
+        },
+        algorithm,
+    })
+}
+
+fn invalid_key(kid: &str, reason: &str) -> ConfigError {
+    ConfigError::Invalid {
+        field: "jwt.keys",
+        reason: format!("key '{kid}': {reason}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::{Path, PathBuf},
+        sync::OnceLock,
+    };
+
+    use ed25519_dalek::SigningKey;
+    use rsa::{
+        RsaPrivateKey,
+        pkcs8::{EncodePrivateKey, EncodePublicKey, LineEnding},
+    };
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct TestClaims {
+        sub: String,
+        exp: i64,
+    }
+
+    fn claims() -> TestClaims {
+        TestClaims { sub: "alice".to_string(), exp: i64::from(u32::MAX) }
+    }
+
+    fn validation() -> Validation {
+        let mut validation = Validation::default();
+        validation.validate_aud = false;
+        validation
+    }
+
+    fn key_dir() -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("keys-{}", uuid::Uuid::new_v4()));
+        fs::create_dir(&dir).unwrap();
+        dir
+    }
+
+    fn write_key(
+        dir: &Path,
+        kid: &str,
+        algorithm: Algorithm,
+        (private_pem, public_pem): (String, String),
+    ) -> JwtKeyConfig {
+        let public_key_path = dir.join(format!("{kid}.pub.pem"));
+        let private_key_path = dir.join(format!("{kid}.pem"));
+        fs::write(&public_key_path, public_pem).unwrap();
+        fs::write(&private_key_path, private_pem).unwrap();
+
+        JwtKeyConfig {
+            kid: kid.to_string(),
+            algorithm,
+            public_key_path: public_key_path.display().to_string(),
+            private_key_path: Some(private_key_path.display().to_string()),
+        }
+    }
+
+    fn rsa_key(dir: &Path, kid: &str) -> JwtKeyConfig {
+        // Генерация RSA в debug-сборке медленная, один ключ на все тесты
+        static PRIVATE_KEY: OnceLock<RsaPrivateKey> = OnceLock::new();
+        let private_key = PRIVATE_KEY.get_or_init(|| {
+            RsaPrivateKey::new(&mut rand::thread_rng(), 2048).unwrap()
+        });
+        let pems = (
+            private_key.to_pkcs8_pem(LineEnding::LF).unwrap().to_string(),
+            private_key
+                .to_public_key()
+                .to_public_key_pem(LineEnding::LF)
+                .unwrap(),
+        );
+        write_key(dir, kid, Algorithm::RS256, pems)
+    }
+
+    fn ed25519_key(dir: &Path, kid: &str) -> JwtKeyConfig {
+        let signing_key = SigningKey::from_bytes(&rand::random());
+        let pems = (
+            signing_key.to_pkcs8_pem(LineEnding::LF).unwrap().to_string(),
+            signing_key
+                .verifying_key()
+                .to_public_key_pem(LineEnding::LF)
+                .unwrap(),
+        );
+        write_key(dir, kid, Algorithm::EdDSA, pems)
+    }
+
+    fn key_ring(signing_kid: &str, keys: Vec<JwtKeyConfig>) -> KeyRing {
+        KeyRing::from_config(&JwtConfig {
+            signing_kid: signing_kid.to_string(),
+            keys,
+            ..JwtConfig::default()
+        })
+        .unwrap()
+    }
+
+    fn round_trip(key_ring: &KeyRing) -> Header {
+        let token = key_ring.encode(&claims()).unwrap();
+        let decoded: TokenData<TestClaims> =
+            key_ring.decode(&token, validation()).unwrap();
+
+        assert_eq!(decoded.claims, claims());
+        decoded.header
+    }
+
+    #[test]
+    fn hs256_round_trip() {
+        let key_ring = KeyRing::from_config(&JwtConfig {
+            secret: "s".repeat(32),
+            ..JwtConfig::default()
+        })
+        .unwrap();
+
+        let header = round_trip(&key_ring);
+        assert_eq!(header.alg, Algorithm::HS256);
+        assert_eq!(header.kid.as_deref(), Some("default"));
+        assert!(key_ring.jwks().keys.is_empty());
+    }
+
+    #[test]
+    fn rs256_round_trip() {
+        let dir = key_dir();
+        let key_ring = key_ring("rsa-1", vec![rsa_key(&dir, "rsa-1")]);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let header = round_trip(&key_ring);
+        assert_eq!(header.alg, Algorithm::RS256);
+        assert_eq!(header.kid.as_deref(), Some("rsa-1"));
+        assert_eq!(key_ring.jwks().keys.len(), 1);
+    }
+
+    #[test]
+    fn ed25519_round_trip() {
+        let dir = key_dir();
+        let key_ring = key_ring("ed-1", vec![ed25519_key(&dir, "ed-1")]);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let header = round_trip(&key_ring);
+        assert_eq!(header.alg, Algorithm::EdDSA);
+        assert_eq!(header.kid.as_deref(), Some("ed-1"));
+    }
+
+    #[test]
+    fn signs_with_the_configured_kid() {
+        let dir = key_dir();
+        let keys = vec![ed25519_key(&dir, "ed-1"), ed25519_key(&dir, "ed-2")];
+        let first = key_ring("ed-1", keys.clone());
+        let second = key_ring("ed-2", keys);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(round_trip(&first).kid.as_deref(), Some("ed-1"));
+        assert_eq!(round_trip(&second).kid.as_deref(), Some("ed-2"));
+        // Каждое кольцо проверяет токены другого по kid
+        let token = second.encode(&claims()).unwrap();
+        assert!(first.decode::<TestClaims>(&token, validation()).is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_kid() {
+        let dir = key_dir();
+        let other = key_ring("ed-other", vec![ed25519_key(&dir, "ed-other")]);
+        let key_ring = key_ring("ed-1", vec![ed25519_key(&dir, "ed-1")]);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let token = other.encode(&claims()).unwrap();
+        let error =
+            key_ring.decode::<TestClaims>(&token, validation()).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidToken);
+    }
+
+    #[test]
+    fn retired_key_still_verifies() {
+        let dir = key_dir();
+        let old_key = ed25519_key(&dir, "ed-old");
+        let old_ring = key_ring("ed-old", vec![old_key.clone()]);
+        // После ротации у старого ключа остаётся только публичная часть
+        let retired = JwtKeyConfig { private_key_path: None, ..old_key };
+        let new_ring =
+            key_ring("rsa-new", vec![rsa_key(&dir, "rsa-new"), retired]);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let token = old_ring.encode(&claims()).unwrap();
+        let decoded =
+            new_ring.decode::<TestClaims>(&token, validation()).unwrap();
+        assert_eq!(decoded.header.kid.as_deref(), Some("ed-old"));
+        assert_eq!(round_trip(&new_ring).kid.as_deref(), Some("rsa-new"));
+        assert_eq!(new_ring.jwks().keys.len(), 2);
+    }
+}
@@ -0,0 +1,132 @@
+use sqlx::{Error as SqlxError, PgPool};
+use time::OffsetDateTime;
+
+use crate::{
+    config::app_config::LoginThrottleConfig, errors::auth_errors::AuthError,
+    models::login_throttle_models::ThrottleScope,
+    repositories::login_throttle_repository::LoginThrottleRepository,
+};
+
+pub struct LoginThrottleService;
+
+impl LoginThrottleService {
+    /// Rejects the attempt while the username or the client IP is locked.
+    pub async fn check(
+        pool: &PgPool,
+        username: &str,
+        ip_address: Option<&str>,
+    ) -> Result<(), AuthError> {
+        let Some(locked_until) = LoginThrottleRepository::find_locked_until(
+            pool, username, ip_address,
+        )
+        .await?
+        else {
+            return Ok(());
+        };
+
+        let remaining = locked_until - OffsetDateTime::now_utc();
+        // Округляем вверх, чтобы клиент не вернулся раньше времени
+        let retry_after_secs = remaining.whole_seconds()
+            + i64::from(remaining.subsec_nanoseconds() > 0);
+
+        log::warn!(
+            "Login for '{username}' rejected, locked for {retry_after_secs}s"
+        );
+        Err(AuthError::LoginLocked {
+            retry_after_secs: retry_after_secs.max(1),
+        })
+    }
+
+    pub async fn record_failure(
+        pool: &PgPool,
+        config: &LoginThrottleConfig,
+        username: &str,
+        ip_address: Option<&str>,
+    ) -> Result<(), AuthError> {
+        let now = OffsetDateTime::now_utc();
+
+        let failures = LoginThrottleRepository::record_failure(
+            pool,
+            ThrottleScope::Username,
+            username,
+            config.reset_after_secs,
+        )
+        .await?;
+        LoginThrottleRepository::lock(
+            pool,
+            ThrottleScope::Username,
+            username,
+            now + config.username_lock(failures),
+        )
+        .await?;
+
+        let Some(ip_address) = ip_address else {
+            return Ok(());
+        };
+        let failures = LoginThrottleRepository::record_failure(
+            pool,
+            ThrottleScope::Ip,
+            ip_address,
+            config.reset_after_secs,
+        )
+        .await?;
+        if let Some(lock) = config.ip_lock(failures) {
+            log::warn!(
+                "Client {ip_address} locked out after {failures} failed logins"
+            );
+            LoginThrottleRepository::lock(
+                pool,
+                ThrottleScope::Ip,
+                ip_address,
+                now + lock,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// A successful login clears the failures of the username. The IP
+    /// counter is kept, it may be shared with someone guessing passwords.
+    pub async fn record_success(
+        pool: &PgPool,
+        username: &str,
+    ) -> Result<(), AuthError> {
+        LoginThrottleRepository::clear(pool, ThrottleScope::Username, username)
+            .await?;
+        Ok(())
+    }
+
+    /// Lifts the lock of a username and/or a client IP. Returns the number
+    /// of counters removed.
+    pub async fn unlock(
+        pool: &PgPool,
+        username: Option<&str>,
+        ip_address: Option<&str>,
+    ) -> Result<u64, SqlxError> {
+        let mut unlocked = 0;
+
+        if let Some(username) = username
+            && LoginThrottleRepository::clear(
+                pool,
+                ThrottleScope::Username,
+                username,
+            )
+            .await?
+        {
+            unlocked += 1;
+        }
+        if let Some(ip_address) = ip_address
+            && LoginThrottleRepository::clear(
+                pool,
+                ThrottleScope::Ip,
+                ip_address,
+            )
+            .await?
+        {
+            unlocked += 1;
+        }
+
+        Ok(unlocked)
+    }
+}
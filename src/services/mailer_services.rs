@@ -0,0 +1,160 @@
+use std::{fs, path::PathBuf, sync::Arc};
+
+use actix_web::web::{self, Data};
+use lettre::{
+    Message, SmtpTransport, Transport,
+    message::{Mailbox, header::ContentType},
+    transport::smtp::authentication::Credentials,
+};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    config::app_config::{MailConfig, MailTransport},
+    errors::{config_errors::ConfigError, mail_errors::MailError},
+};
+
+const REDACTED_TOKEN: &str = "[token redacted, use the file transport]";
+
+#[derive(Debug)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+    /// Token carried in the body, masked where mail is only logged.
+    pub token: Option<String>,
+}
+
+/// Delivers mail. Implementations may block, handlers go through
+/// [`MailService::send`], which runs them on the blocking thread pool.
+pub trait Mailer: Send + Sync {
+    fn send(&self, email: &Email) -> Result<(), MailError>;
+}
+
+/// Writes mails to the application log with their token masked, since the
+/// log may be collected elsewhere. Only meant for local development.
+pub struct LogMailer;
+
+/// Writes every mail to an `.eml` file, so tests can pick up the tokens.
+pub struct FileMailer {
+    from: Mailbox,
+    dir: PathBuf,
+}
+
+pub struct SmtpMailer {
+    from: Mailbox,
+    transport: SmtpTransport,
+}
+
+impl Mailer for LogMailer {
+    fn send(&self, email: &Email) -> Result<(), MailError> {
+        let body = match email.token.as_deref() {
+            Some(token) if !token.is_empty() => {
+                email.body.replace(token, REDACTED_TOKEN)
+            }
+            _ => email.body.clone(),
+        };
+
+        log::info!(
+            "Mail to {}, subject '{}':\n{body}",
+            email.to,
+            email.subject
+        );
+        Ok(())
+    }
+}
+
+impl Mailer for FileMailer {
+    fn send(&self, email: &Email) -> Result<(), MailError> {
+        let message = build_message(&self.from, email)?;
+        let path = self.dir.join(format!(
+            "{}-{}.eml",
+            OffsetDateTime::now_utc().unix_timestamp(),
+            Uuid::new_v4()
+        ));
+
+        fs::create_dir_all(&self.dir)?;
+        fs::write(&path, message.formatted())?;
+        log::info!("Mail to {} written to {}", email.to, path.display());
+        Ok(())
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send(&self, email: &Email) -> Result<(), MailError> {
+        let message = build_message(&self.from, email)?;
+        self.transport.send(&message)?;
+
+        log::info!("Mail '{}' sent to {}", email.subject, email.to);
+        Ok(())
+    }
+}
+
+fn build_message(from: &Mailbox, email: &Email) -> Result<Message, MailError> {
+    Ok(Message::builder()
+        .from(from.clone())
+        .to(email.to.parse()?)
+        .subject(&email.subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(email.body.clone())?)
+}
+
+pub struct MailService;
+
+impl MailService {
+    pub fn from_config(
+        config: &MailConfig,
+    ) -> Result<Arc<dyn Mailer>, ConfigError> {
+        let from: Mailbox = config.from.parse().map_err(|e| {
+            ConfigError::Invalid { field: "mail.from", reason: format!("{e}") }
+        })?;
+
+        let mailer: Arc<dyn Mailer> = match config.transport {
+            MailTransport::Log => Arc::new(LogMailer),
+            MailTransport::File => Arc::new(FileMailer {
+                from,
+                dir: PathBuf::from(&config.file_dir),
+            }),
+            MailTransport::Smtp => {
+                let mut builder =
+                    SmtpTransport::starttls_relay(&config.smtp_host)
+                        .map_err(|e| ConfigError::Invalid {
+                            field: "mail.smtp_host",
+                            reason: e.to_string(),
+                        })?
+                        .port(config.smtp_port);
+
+                if let (Some(username), Some(password)) =
+                    (&config.smtp_username, &config.smtp_password)
+                {
+                    builder = builder.credentials(Credentials::new(
+                        username.clone(),
+                        password.clone(),
+                    ));
+                }
+
+                Arc::new(SmtpMailer { from, transport: builder.build() })
+            }
+        };
+
+        if config.transport == MailTransport::Log {
+            log::warn!(
+                "Mail transport 'log' delivers no mail, password reset and \
+                 email verification do not work. Set mail.transport to \
+                 'smtp' outside development"
+            );
+        } else {
+            log::info!("Mail transport: {:?}", config.transport);
+        }
+        Ok(mailer)
+    }
+
+    pub async fn send(
+        mailer: Data<dyn Mailer>,
+        email: Email,
+    ) -> Result<(), MailError> {
+        web::block(move || mailer.send(&email))
+            .await
+            .map_err(|e| MailError::Blocking(e.to_string()))?
+    }
+}
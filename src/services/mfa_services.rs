@@ -0,0 +1,204 @@
+use sqlx::{Error as SqlxError, PgPool};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    config::app_config::AppConfig,
+    errors::mfa_errors::MfaError,
+    models::{
+        auth_models::ClientInfo,
+        mfa_models::{
+            MFA_PENDING, MfaChallenge, RecoveryCodes, TotpEnrollment,
+        },
+    },
+    repositories::mfa_repository::MfaRepository,
+    services::{
+        login_throttle_services::LoginThrottleService,
+        token_hash_services::TokenHashService, totp_services::TotpService,
+    },
+};
+
+pub struct MfaService;
+
+impl MfaService {
+    /// Generates a new secret for the user. It only takes effect once a
+    /// code computed from it is confirmed.
+    pub async fn enroll(
+        pool: &PgPool,
+        config: &AppConfig,
+        user_id: i32,
+    ) -> Result<TotpEnrollment, MfaError> {
+        let state = MfaRepository::find_totp(pool, user_id)
+            .await?
+            .ok_or(MfaError::Unauthorized)?;
+        if state.totp_enabled {
+            return Err(MfaError::AlreadyEnabled);
+        }
+
+        let secret = TotpService::generate_secret();
+        if !MfaRepository::set_pending_secret(pool, user_id, &secret).await? {
+            return Err(MfaError::AlreadyEnabled);
+        }
+
+        let otpauth_uri = TotpService::otpauth_uri(
+            &config.mfa.issuer,
+            &state.username,
+            &secret,
+        );
+        Ok(TotpEnrollment { secret, otpauth_uri })
+    }
+
+    /// Enables 2FA once the user proves their app produces valid codes and
+    /// returns the recovery codes, which are shown only this once.
+    pub async fn confirm(
+        pool: &PgPool,
+        config: &AppConfig,
+        user_id: i32,
+        code: &str,
+        client: &ClientInfo,
+    ) -> Result<RecoveryCodes, MfaError> {
+        let state = MfaRepository::find_totp(pool, user_id)
+            .await?
+            .ok_or(MfaError::Unauthorized)?;
+        if state.totp_enabled {
+            return Err(MfaError::AlreadyEnabled);
+        }
+        let secret = state.totp_secret.ok_or(MfaError::NotEnrolled)?;
+
+        let ip_address = client.ip_address.as_deref();
+        LoginThrottleService::check(pool, &state.username, ip_address).await?;
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let Some(step) = TotpService::verify(&secret, code, now) else {
+            LoginThrottleService::record_failure(
+                pool,
+                &config.login_throttle,
+                &state.username,
+                ip_address,
+            )
+            .await?;
+            return Err(MfaError::InvalidCode);
+        };
+        LoginThrottleService::record_success(pool, &state.username).await?;
+
+        let recovery_codes: Vec<String> = (0..config.mfa.recovery_codes)
+            .map(|_| TotpService::generate_recovery_code())
+            .collect();
+        let hashes: Vec<String> = recovery_codes
+            .iter()
+            .map(|code| Self::hash_recovery_code(config, code))
+            .collect();
+
+        if !MfaRepository::enable(pool, user_id, step, &hashes).await? {
+            return Err(MfaError::AlreadyEnabled);
+        }
+
+        Ok(RecoveryCodes { recovery_codes })
+    }
+
+    /// Wrong codes count towards the login lock, so a stolen access token
+    /// can't be used to guess them.
+    pub async fn disable(
+        pool: &PgPool,
+        config: &AppConfig,
+        user_id: i32,
+        code: &str,
+        client: &ClientInfo,
+    ) -> Result<(), MfaError> {
+        let state = MfaRepository::find_totp(pool, user_id)
+            .await?
+            .ok_or(MfaError::Unauthorized)?;
+        if !state.totp_enabled {
+            return Err(MfaError::NotEnabled);
+        }
+
+        let ip_address = client.ip_address.as_deref();
+        LoginThrottleService::check(pool, &state.username, ip_address).await?;
+        if !Self::verify_code(pool, config, user_id, code).await? {
+            LoginThrottleService::record_failure(
+                pool,
+                &config.login_throttle,
+                &state.username,
+                ip_address,
+            )
+            .await?;
+            return Err(MfaError::InvalidCode);
+        }
+        LoginThrottleService::record_success(pool, &state.username).await?;
+
+        MfaRepository::disable(pool, user_id).await?;
+        Ok(())
+    }
+
+    pub async fn is_enabled(
+        pool: &PgPool,
+        user_id: i32,
+    ) -> Result<bool, SqlxError> {
+        let state = MfaRepository::find_totp(pool, user_id).await?;
+        Ok(state.is_some_and(|state| state.totp_enabled))
+    }
+
+    /// Accepts a current TOTP code or an unused recovery code. Either is
+    /// consumed, so the same code never works twice.
+    pub async fn verify_code(
+        pool: &PgPool,
+        config: &AppConfig,
+        user_id: i32,
+        code: &str,
+    ) -> Result<bool, SqlxError> {
+        let Some(state) = MfaRepository::find_totp(pool, user_id).await? else {
+            return Ok(false);
+        };
+        let (true, Some(secret)) = (state.totp_enabled, state.totp_secret)
+        else {
+            return Ok(false);
+        };
+
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        if let Some(step) = TotpService::verify(&secret, code, now) {
+            return MfaRepository::use_step(pool, user_id, step).await;
+        }
+
+        let code_hash = Self::hash_recovery_code(config, code);
+        MfaRepository::use_recovery_code(pool, user_id, &code_hash).await
+    }
+
+    /// Issues the `mfa_pending` token a client exchanges for tokens at
+    /// `/login/mfa` together with a code.
+    pub async fn create_challenge(
+        pool: &PgPool,
+        config: &AppConfig,
+        user_id: i32,
+        scope: Option<&str>,
+        audience: Option<&str>,
+    ) -> Result<MfaChallenge, SqlxError> {
+        let mfa_token = Uuid::new_v4().to_string();
+        let token_hash = TokenHashService::hash(
+            &config.jwt.refresh_token_hmac_key,
+            &mfa_token,
+        );
+        let expires_at = OffsetDateTime::now_utc() + config.mfa.challenge_ttl();
+
+        MfaRepository::create_challenge(
+            pool,
+            &token_hash,
+            user_id,
+            scope,
+            audience,
+            expires_at,
+        )
+        .await?;
+
+        Ok(MfaChallenge {
+            token_type: MFA_PENDING,
+            mfa_token,
+            expires_in: config.mfa.challenge_ttl_secs,
+        })
+    }
+
+    fn hash_recovery_code(config: &AppConfig, code: &str) -> String {
+        TokenHashService::hash(
+            &config.jwt.refresh_token_hmac_key,
+            &TotpService::normalize_recovery_code(code),
+        )
+    }
+}
@@ -1 +1,18 @@
+pub mod account_services;
+pub mod audit_services;
 pub mod auth_services;
+pub mod cookie_services;
+pub mod denylist_services;
+pub mod key_ring_services;
+pub mod login_throttle_services;
+pub mod mailer_services;
+pub mod mfa_services;
+pub mod password_policy_services;
+pub mod password_services;
+pub mod personal_tokens_services;
+pub mod roles_services;
+pub mod scheduler_services;
+pub mod scope_services;
+pub mod token_hash_services;
+pub mod totp_services;
+pub mod user_id_cache_services;
@@ -0,0 +1,317 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use sha1::{Digest, Sha1};
+
+use crate::{
+    config::app_config::PasswordPolicyConfig,
+    errors::{
+        config_errors::ConfigError, password_policy_errors::PasswordPolicyError,
+    },
+    models::password_policy_models::{
+        BREACHED, CONTAINS_USERNAME, MIN_ENTROPY,
+    },
+};
+
+/// Hex digits of the SHA-1 hash that name a range file.
+const RANGE_PREFIX_LEN: usize = 5;
+/// Usernames shorter than this are too likely to occur by chance.
+const MIN_USERNAME_LEN: usize = 3;
+
+enum BreachedPasswords {
+    Disabled,
+    /// Full hashes of a list small enough to keep in memory.
+    Hashes(HashSet<String>),
+    /// Range files with the hash suffixes of one prefix each. Only the file
+    /// of the password's prefix is read.
+    Ranges(PathBuf),
+}
+
+/// The password policy, with the breached password list loaded at startup.
+pub struct PasswordPolicy {
+    min_entropy_bits: f64,
+    reject_username: bool,
+    breached: BreachedPasswords,
+}
+
+impl PasswordPolicy {
+    pub fn from_config(
+        config: &PasswordPolicyConfig,
+    ) -> Result<Self, ConfigError> {
+        let breached = match config.breached_passwords_path.as_deref() {
+            None | Some("") => BreachedPasswords::Disabled,
+            Some(path) if Path::new(path).is_dir() => {
+                BreachedPasswords::Ranges(PathBuf::from(path))
+            }
+            Some(path) => BreachedPasswords::Hashes(Self::load_hashes(path)?),
+        };
+
+        Ok(PasswordPolicy {
+            min_entropy_bits: config.min_entropy_bits,
+            reject_username: config.reject_username,
+            breached,
+        })
+    }
+
+    /// Checks a new password of `username` against every rule.
+    pub async fn check(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<(), PasswordPolicyError> {
+        let mut violations = Vec::new();
+
+        if Self::entropy_bits(password) < self.min_entropy_bits {
+            violations.push(MIN_ENTROPY);
+        }
+        if self.reject_username
+            && username.chars().count() >= MIN_USERNAME_LEN
+            && password.to_lowercase().contains(&username.to_lowercase())
+        {
+            violations.push(CONTAINS_USERNAME);
+        }
+        if self.is_breached(password).await {
+            violations.push(BREACHED);
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(PasswordPolicyError(violations))
+        }
+    }
+
+    /// Rough brute force cost: the size of the character classes used,
+    /// per character. Repeated characters in a row count once, so
+    /// `aaaaaaaaaaaa` scores like `a`.
+    fn entropy_bits(password: &str) -> f64 {
+        let (mut lower, mut upper, mut digit, mut symbol, mut other) =
+            (false, false, false, false, false);
+        let mut length = 0u32;
+        let mut previous = None;
+
+        for c in password.chars() {
+            match c {
+                'a'..='z' => lower = true,
+                'A'..='Z' => upper = true,
+                '0'..='9' => digit = true,
+                c if c.is_ascii() => symbol = true,
+                _ => other = true,
+            }
+            if previous != Some(c) {
+                length += 1;
+            }
+            previous = Some(c);
+        }
+
+        let pool =
+            [(lower, 26), (upper, 26), (digit, 10), (symbol, 33), (other, 100)]
+                .into_iter()
+                .filter_map(|(used, size)| used.then_some(size))
+                .sum::<u32>();
+        if pool == 0 {
+            return 0.0;
+        }
+
+        f64::from(length) * f64::from(pool).log2()
+    }
+
+    async fn is_breached(&self, password: &str) -> bool {
+        let hash = hex::encode_upper(Sha1::digest(password.as_bytes()));
+
+        match &self.breached {
+            BreachedPasswords::Disabled => false,
+            BreachedPasswords::Hashes(hashes) => hashes.contains(&hash),
+            BreachedPasswords::Ranges(dir) => {
+                let (prefix, suffix) = hash.split_at(RANGE_PREFIX_LEN);
+                let path = dir.join(format!("{prefix}.txt"));
+
+                match tokio::fs::read_to_string(&path).await {
+                    Ok(range) => range
+                        .lines()
+                        .filter_map(Self::parse_hash)
+                        .any(|entry| entry == suffix),
+                    Err(e) if e.kind() == ErrorKind::NotFound => false,
+                    Err(e) => {
+                        // Недоступный список не должен блокировать смену
+                        // пароля, остальные правила всё равно проверены
+                        log::error!(
+                            "Failed to read breached password range {}: {e}",
+                            path.display()
+                        );
+                        false
+                    }
+                }
+            }
+        }
+    }
+
+    fn load_hashes(path: &str) -> Result<HashSet<String>, ConfigError> {
+        let content = fs::read_to_string(path).map_err(|source| {
+            ConfigError::Read { path: path.to_string(), source }
+        })?;
+
+        let hashes: HashSet<String> = content
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                Self::parse_hash(line)
+                    .filter(|hash| hash.len() == 40)
+                    .ok_or_else(|| ConfigError::Invalid {
+                        field: "password_policy.breached_passwords_path",
+                        reason: format!("'{line}' is not a SHA-1 hash"),
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+
+        log::info!("Loaded {} breached password hashes", hashes.len());
+        Ok(hashes)
+    }
+
+    /// The hash of a `HASH` or `HASH:COUNT` line, upper case.
+    fn parse_hash(line: &str) -> Option<String> {
+        let hash = line.split(':').next()?.trim();
+        (!hash.is_empty() && hash.chars().all(|c| c.is_ascii_hexdigit()))
+            .then(|| hash.to_ascii_uppercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(breached_passwords_path: Option<String>) -> PasswordPolicy {
+        PasswordPolicy::from_config(&PasswordPolicyConfig {
+            min_entropy_bits: 40.0,
+            reject_username: true,
+            breached_passwords_path,
+        })
+        .unwrap()
+    }
+
+    async fn violations(
+        policy: &PasswordPolicy,
+        username: &str,
+        password: &str,
+    ) -> Vec<&'static str> {
+        match policy.check(username, password).await {
+            Ok(()) => Vec::new(),
+            Err(PasswordPolicyError(violations)) => {
+                violations.iter().map(|v| v.rule).collect()
+            }
+        }
+    }
+
+    fn sha1_hex(password: &str) -> String {
+        hex::encode_upper(Sha1::digest(password.as_bytes()))
+    }
+
+    #[test]
+    fn entropy_grows_with_length_and_character_classes() {
+        assert!(PasswordPolicy::entropy_bits("").abs() < f64::EPSILON);
+
+        let digits = PasswordPolicy::entropy_bits("1234567890");
+        assert!((digits - 10.0 * 10f64.log2()).abs() < 1e-9);
+
+        let lower = PasswordPolicy::entropy_bits("abcdefgh");
+        let mixed = PasswordPolicy::entropy_bits("abcdEFG1");
+        assert!(mixed > lower);
+        assert!(PasswordPolicy::entropy_bits("abcdefghij") > lower);
+    }
+
+    #[test]
+    fn repeated_characters_count_once() {
+        assert!(
+            (PasswordPolicy::entropy_bits("aaaaaaaaaaaa")
+                - PasswordPolicy::entropy_bits("a"))
+            .abs()
+                < f64::EPSILON
+        );
+        assert!(
+            PasswordPolicy::entropy_bits("abab")
+                > PasswordPolicy::entropy_bits("aabb")
+        );
+    }
+
+    #[actix_web::test]
+    async fn check_reports_every_violation() {
+        let policy = policy(None);
+
+        assert!(
+            violations(&policy, "alice", "Zq8#vLw2-pR5mK").await.is_empty()
+        );
+        assert_eq!(violations(&policy, "alice", "aaaa").await, ["min_entropy"]);
+        assert_eq!(
+            violations(&policy, "alice", "xALICEx").await,
+            ["min_entropy", "contains_username"]
+        );
+        assert_eq!(
+            violations(&policy, "alice", "Zq8#vLw2-Alice").await,
+            ["contains_username"]
+        );
+    }
+
+    #[actix_web::test]
+    async fn short_usernames_are_not_rejected() {
+        let policy = policy(None);
+
+        assert!(violations(&policy, "al", "Zq8#vLw2-alR5mK").await.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn check_rejects_passwords_from_hash_list() {
+        let path = std::env::temp_dir()
+            .join(format!("breached-{}.txt", uuid::Uuid::new_v4()));
+        fs::write(
+            &path,
+            format!("# comment\n{}:42\n", sha1_hex("Zq8#vLw2-pR5mK")),
+        )
+        .unwrap();
+        let policy = policy(Some(path.display().to_string()));
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            violations(&policy, "alice", "Zq8#vLw2-pR5mK").await,
+            ["breached"]
+        );
+        assert!(
+            violations(&policy, "alice", "Zq8#vLw2-pR5mX").await.is_empty()
+        );
+    }
+
+    #[actix_web::test]
+    async fn check_rejects_passwords_from_range_files() {
+        let dir = std::env::temp_dir()
+            .join(format!("breached-{}", uuid::Uuid::new_v4()));
+        fs::create_dir(&dir).unwrap();
+        let hash = sha1_hex("Zq8#vLw2-pR5mK");
+        let (prefix, suffix) = hash.split_at(RANGE_PREFIX_LEN);
+        fs::write(dir.join(format!("{prefix}.txt")), format!("{suffix}:3\n"))
+            .unwrap();
+        let policy = policy(Some(dir.display().to_string()));
+
+        let breached = violations(&policy, "alice", "Zq8#vLw2-pR5mK").await;
+        let missing_range =
+            violations(&policy, "alice", "Zq8#vLw2-pR5mX").await;
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(breached, ["breached"]);
+        assert!(missing_range.is_empty());
+    }
+
+    #[test]
+    fn load_hashes_rejects_malformed_lines() {
+        let path = std::env::temp_dir()
+            .join(format!("breached-{}.txt", uuid::Uuid::new_v4()));
+        fs::write(&path, "not-a-hash\n").unwrap();
+
+        let result = PasswordPolicy::load_hashes(&path.display().to_string());
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(ConfigError::Invalid { .. })));
+    }
+}
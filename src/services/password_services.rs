@@ -0,0 +1,190 @@
+use std::sync::OnceLock;
+
+use argon2::{
+    ARGON2ID_IDENT, Algorithm, Argon2, Params, PasswordHash, PasswordHasher,
+    PasswordVerifier, Version,
+    password_hash::{Error as PasswordHashError, SaltString, rand_core::OsRng},
+};
+use subtle::ConstantTimeEq;
+
+use crate::config::app_config::PasswordConfig;
+
+/// Default written into `users.password` by migration 0002 for rows that
+/// existed before passwords were introduced. It never grants access.
+pub const LEGACY_DEFAULT_PASSWORD: &str = "temp_password";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PasswordVerification {
+    Invalid,
+    Valid { needs_rehash: bool },
+}
+
+pub struct PasswordService;
+
+impl PasswordService {
+    /// Hashes a password with Argon2id and returns it as a PHC string.
+    pub fn hash(
+        config: &PasswordConfig,
+        password: &str,
+    ) -> Result<String, PasswordHashError> {
+        let salt = SaltString::generate(&mut OsRng);
+
+        Self::argon2(config)?
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+    }
+
+    /// Checks a password against a stored value, which is either a PHC
+    /// string or a legacy plaintext password.
+    pub fn verify(
+        config: &PasswordConfig,
+        password: &str,
+        stored: &str,
+    ) -> Result<PasswordVerification, PasswordHashError> {
+        if stored == LEGACY_DEFAULT_PASSWORD {
+            log::warn!("Login attempt on account without a usable password");
+            Self::verify_dummy(config, password);
+            return Ok(PasswordVerification::Invalid);
+        }
+
+        let Ok(hash) = PasswordHash::new(stored) else {
+            // Rows written before hashing was introduced hold plaintext
+            Self::verify_dummy(config, password);
+            return Ok(
+                if bool::from(password.as_bytes().ct_eq(stored.as_bytes())) {
+                    PasswordVerification::Valid { needs_rehash: true }
+                } else {
+                    PasswordVerification::Invalid
+                },
+            );
+        };
+
+        let argon2 = Self::argon2(config)?;
+        match argon2.verify_password(password.as_bytes(), &hash) {
+            Ok(()) => Ok(PasswordVerification::Valid {
+                needs_rehash: Self::needs_rehash(argon2.params(), &hash),
+            }),
+            Err(PasswordHashError::Password) => {
+                Ok(PasswordVerification::Invalid)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Verifies against a throwaway hash and ignores the result, so a
+    /// login without a stored hash to check takes as long as one with a
+    /// wrong password. The hash is created on first use, which `main` does
+    /// at startup.
+    pub fn verify_dummy(config: &PasswordConfig, password: &str) {
+        static DUMMY_HASH: OnceLock<Option<String>> = OnceLock::new();
+
+        let dummy = DUMMY_HASH.get_or_init(|| {
+            Self::hash(config, "dummy password")
+                .inspect_err(|e| {
+                    log::error!("Failed to create dummy password hash: {e}");
+                })
+                .ok()
+        });
+        if let Some(dummy) =
+            dummy.as_deref().and_then(|h| PasswordHash::new(h).ok())
+            && let Ok(argon2) = Self::argon2(config)
+        {
+            let _ = argon2.verify_password(password.as_bytes(), &dummy);
+        }
+    }
+
+    fn argon2(
+        config: &PasswordConfig,
+    ) -> Result<Argon2<'static>, PasswordHashError> {
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, config.params()?))
+    }
+
+    /// A hash needs upgrading when it is not Argon2id or was produced with
+    /// weaker parameters than the ones currently configured.
+    fn needs_rehash(current: &Params, hash: &PasswordHash) -> bool {
+        if hash.algorithm != ARGON2ID_IDENT
+            || hash.version != Some(Version::V0x13.into())
+        {
+            return true;
+        }
+
+        Params::try_from(hash).map_or(true, |params| {
+            params.m_cost() < current.m_cost()
+                || params.t_cost() < current.t_cost()
+                || params.p_cost() < current.p_cost()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Минимальная стоимость, чтобы тесты не тратили время на Argon2
+    fn config(memory_cost: u32) -> PasswordConfig {
+        PasswordConfig { memory_cost, time_cost: 1, parallelism: 1 }
+    }
+
+    #[test]
+    fn hashed_password_verifies() {
+        let config = config(1024);
+        let hash = PasswordService::hash(&config, "correct horse").unwrap();
+
+        assert_eq!(
+            PasswordService::verify(&config, "correct horse", &hash).unwrap(),
+            PasswordVerification::Valid { needs_rehash: false }
+        );
+        assert_eq!(
+            PasswordService::verify(&config, "wrong horse", &hash).unwrap(),
+            PasswordVerification::Invalid
+        );
+    }
+
+    #[test]
+    fn legacy_plaintext_verifies_and_needs_upgrade() {
+        let config = config(1024);
+
+        assert_eq!(
+            PasswordService::verify(&config, "password123", "password123")
+                .unwrap(),
+            PasswordVerification::Valid { needs_rehash: true }
+        );
+        assert_eq!(
+            PasswordService::verify(&config, "password12", "password123")
+                .unwrap(),
+            PasswordVerification::Invalid
+        );
+    }
+
+    #[test]
+    fn legacy_default_password_never_verifies() {
+        let config = config(1024);
+
+        assert_eq!(
+            PasswordService::verify(
+                &config,
+                LEGACY_DEFAULT_PASSWORD,
+                LEGACY_DEFAULT_PASSWORD
+            )
+            .unwrap(),
+            PasswordVerification::Invalid
+        );
+    }
+
+    #[test]
+    fn weaker_params_need_rehash() {
+        let weak = config(1024);
+        let hash = PasswordService::hash(&weak, "correct horse").unwrap();
+
+        assert_eq!(
+            PasswordService::verify(&config(2048), "correct horse", &hash)
+                .unwrap(),
+            PasswordVerification::Valid { needs_rehash: true }
+        );
+        let stronger = PasswordConfig { time_cost: 2, ..weak };
+        assert_eq!(
+            PasswordService::verify(&stronger, "correct horse", &hash).unwrap(),
+            PasswordVerification::Valid { needs_rehash: true }
+        );
+    }
+}
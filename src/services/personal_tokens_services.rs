@@ -0,0 +1,119 @@
+use rand::RngCore;
+use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::{
+    config::app_config::AppConfig,
+    errors::{
+        auth_errors::AuthError, personal_tokens_errors::PersonalTokenError,
+    },
+    models::{
+        auth_models::Claims,
+        personal_tokens_models::{
+            CreatePersonalToken, NewPersonalToken, PERSONAL_TOKEN_PREFIX,
+        },
+        scope_models,
+    },
+    repositories::{
+        personal_tokens_repository::PersonalTokensRepository,
+        roles_repository::RolesRepository,
+    },
+    services::{
+        scope_services::ScopeService, token_hash_services::TokenHashService,
+    },
+};
+
+/// Characters of a token kept in clear text to identify it in listings.
+const DISPLAY_PREFIX_LEN: usize = PERSONAL_TOKEN_PREFIX.len() + 8;
+
+pub struct PersonalTokenService;
+
+impl PersonalTokenService {
+    pub fn is_personal_token(token: &str) -> bool {
+        token.starts_with(PERSONAL_TOKEN_PREFIX)
+    }
+
+    /// Creates a token for the user. The secret is only part of the
+    /// response, the database keeps its hash. A session limited to
+    /// `granted` can only create tokens within that scope.
+    pub async fn create(
+        pool: &PgPool,
+        config: &AppConfig,
+        user_id: i32,
+        granted: Option<&str>,
+        request: CreatePersonalToken,
+    ) -> Result<NewPersonalToken, PersonalTokenError> {
+        let mut scopes = request.scopes;
+        if let Some(unknown) =
+            scopes.iter().find(|scope| !scope_models::is_known(scope))
+        {
+            return Err(PersonalTokenError::UnknownScope(unknown.clone()));
+        }
+        if let Some(widened) = scopes.iter().find(|scope| {
+            granted
+                .is_some_and(|granted| !ScopeService::contains(granted, scope))
+        }) {
+            return Err(PersonalTokenError::ScopeNotGranted(widened.clone()));
+        }
+        scopes.sort();
+        scopes.dedup();
+
+        let token = Self::generate_token();
+        let token_hash =
+            TokenHashService::hash(&config.jwt.refresh_token_hmac_key, &token);
+        let expires_at = request
+            .expires_in_days
+            .map(|days| OffsetDateTime::now_utc() + Duration::days(days));
+
+        let details = PersonalTokensRepository::create(
+            pool,
+            Uuid::new_v4(),
+            user_id,
+            &request.name,
+            &token_hash,
+            &token[..DISPLAY_PREFIX_LEN],
+            &scopes,
+            expires_at,
+        )
+        .await?;
+
+        Ok(NewPersonalToken { details, token })
+    }
+
+    /// Claims for a personal access token, with the current roles of its
+    /// owner.
+    pub async fn authenticate(
+        pool: &PgPool,
+        config: &AppConfig,
+        token: &str,
+    ) -> Result<Claims, AuthError> {
+        let token_hash =
+            TokenHashService::hash(&config.jwt.refresh_token_hmac_key, token);
+
+        let Some(record) =
+            PersonalTokensRepository::use_token(pool, &token_hash).await?
+        else {
+            log::warn!(
+                "Unknown personal access token {}",
+                TokenHashService::fingerprint(&token_hash)
+            );
+            return Err(AuthError::PersonalTokenInvalid);
+        };
+
+        let roles =
+            RolesRepository::find_user_roles(pool, record.user_id).await?;
+        log::debug!(
+            "Personal access token {} validated for user {}",
+            record.id,
+            record.user_id
+        );
+        Ok(Claims::for_personal_token(&config.jwt, &record, roles))
+    }
+
+    fn generate_token() -> String {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        format!("{PERSONAL_TOKEN_PREFIX}{}", hex::encode(bytes))
+    }
+}
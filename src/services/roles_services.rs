@@ -0,0 +1,79 @@
+use sqlx::{Error as SqlxError, PgPool};
+
+use crate::{
+    config::app_config::AdminConfig,
+    errors::admin_errors::AdminError,
+    models::{
+        role_models::{ADMIN_ROLE, RolePermissions, UserRoles},
+        users_models::User,
+    },
+    repositories::roles_repository::RolesRepository,
+    services::denylist_services::TokenDenylist,
+};
+
+pub struct RolesService;
+
+impl RolesService {
+    /// Grants the admin role to the users listed in `admin.user_ids`.
+    /// Unknown ids are skipped with a warning.
+    pub async fn bootstrap_admins(
+        pool: &PgPool,
+        config: &AdminConfig,
+    ) -> Result<(), SqlxError> {
+        for &user_id in &config.user_ids {
+            if RolesRepository::grant(pool, user_id, ADMIN_ROLE)
+                .await?
+                .is_none()
+            {
+                log::warn!("admin.user_ids: user {user_id} does not exist");
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn find(
+        pool: &PgPool,
+        user: &User,
+    ) -> Result<UserRoles, AdminError> {
+        let roles = RolesRepository::find_user_roles(pool, user.id).await?;
+        let permissions =
+            RolesRepository::find_user_permissions(pool, user.id).await?;
+        Ok(UserRoles { user_id: user.public_id, roles, permissions })
+    }
+
+    pub async fn list(
+        pool: &PgPool,
+    ) -> Result<Vec<RolePermissions>, AdminError> {
+        Ok(RolesRepository::find_roles(pool).await?)
+    }
+
+    /// The role shows up in the user's tokens from the next refresh on.
+    pub async fn grant(
+        pool: &PgPool,
+        user: &User,
+        role: &str,
+    ) -> Result<UserRoles, AdminError> {
+        if RolesRepository::grant(pool, user.id, role).await?.is_none() {
+            return Err(AdminError::NotFound(format!("No role '{role}'")));
+        }
+        Self::find(pool, user).await
+    }
+
+    /// Access tokens of the user are revoked, so the role is gone at once.
+    /// Refresh tokens stay valid and get tokens with the remaining roles.
+    pub async fn revoke(
+        pool: &PgPool,
+        denylist: &TokenDenylist,
+        user: &User,
+        role: &str,
+    ) -> Result<UserRoles, AdminError> {
+        if !RolesRepository::revoke(pool, user.id, role).await? {
+            return Err(AdminError::NotFound(format!(
+                "User {} does not have role '{role}'",
+                user.public_id
+            )));
+        }
+        denylist.revoke_user(pool, user.id).await?;
+        Self::find(pool, user).await
+    }
+}
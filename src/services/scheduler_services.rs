@@ -0,0 +1,92 @@
+use actix_web::{rt, web::Data};
+use sqlx::{Error as SqlxError, PgPool};
+use time::{Duration, OffsetDateTime};
+
+use crate::{
+    config::app_config::{AppConfig, CleanupJob},
+    repositories::cleanup_repository::CleanupRepository,
+};
+
+/// Runs the cleanup jobs in the background, each on its own interval.
+pub struct Scheduler;
+
+impl Scheduler {
+    pub fn spawn(config: &Data<AppConfig>, pool: &PgPool) {
+        if !config.scheduler.enabled {
+            log::info!("Scheduler disabled, expired rows are not purged");
+            return;
+        }
+
+        for job in CleanupJob::ALL {
+            let config = config.clone();
+            let pool = pool.clone();
+            rt::spawn(async move {
+                let mut interval =
+                    rt::time::interval(config.scheduler.interval(job));
+                loop {
+                    interval.tick().await;
+                    Self::run(&pool, &config, job).await;
+                }
+            });
+        }
+    }
+
+    async fn run(pool: &PgPool, config: &AppConfig, job: CleanupJob) {
+        match Self::try_run(pool, config, job).await {
+            Ok(Some(deleted)) => {
+                log::info!(
+                    "Cleanup job {} deleted {deleted} rows",
+                    job.as_str()
+                );
+            }
+            Ok(None) => log::debug!(
+                "Cleanup job {} skipped, another instance is running it",
+                job.as_str()
+            ),
+            Err(e) => {
+                log::error!("Cleanup job {} failed: {e}", job.as_str());
+            }
+        }
+    }
+
+    /// `None` if another instance holds the lock of the job.
+    async fn try_run(
+        pool: &PgPool,
+        config: &AppConfig,
+        job: CleanupJob,
+    ) -> Result<Option<u64>, SqlxError> {
+        let mut tx = pool.begin().await?;
+        if !CleanupRepository::try_lock(&mut tx, job).await? {
+            return Ok(None);
+        }
+        CleanupRepository::record_run(&mut tx, job).await?;
+
+        let deleted =
+            CleanupRepository::purge(&mut tx, job, Self::cutoff(config, job))
+                .await?;
+        tx.commit().await?;
+        Ok(Some(deleted))
+    }
+
+    /// Rows that expired before this are of no use anymore.
+    fn cutoff(config: &AppConfig, job: CleanupJob) -> OffsetDateTime {
+        let now = OffsetDateTime::now_utc();
+        // Просроченный токен принимается ещё `leeway_secs`
+        let leeway = config.jwt.leeway();
+
+        match job {
+            CleanupJob::RevokedAccessTokens => now - leeway,
+            // Отсечка нужна, пока живы выданные до неё access токены
+            CleanupJob::AccessTokenCutoffs => {
+                now - config.jwt.access_token_ttl() - leeway
+            }
+            CleanupJob::LoginThrottles => {
+                now - Duration::seconds(config.login_throttle.reset_after_secs)
+            }
+            CleanupJob::RefreshTokens
+            | CleanupJob::MfaChallenges
+            | CleanupJob::UserTokens
+            | CleanupJob::PersonalAccessTokens => now,
+        }
+    }
+}
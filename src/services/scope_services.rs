@@ -0,0 +1,85 @@
+use crate::{errors::auth_errors::AuthError, models::scope_models};
+
+pub struct ScopeService;
+
+impl ScopeService {
+    /// Normalises a requested space-separated scope: known scopes only,
+    /// sorted and without duplicates. When the session was already
+    /// limited to `granted`, the request may only narrow it further.
+    pub fn narrow(
+        requested: &str,
+        granted: Option<&str>,
+    ) -> Result<String, AuthError> {
+        let mut scopes: Vec<&str> = requested.split_whitespace().collect();
+        if scopes.is_empty() {
+            return Err(AuthError::InvalidScope(requested.to_string()));
+        }
+
+        if let Some(scope) = scopes.iter().find(|scope| {
+            !scope_models::is_known(scope)
+                || granted
+                    .is_some_and(|granted| !Self::contains(granted, scope))
+        }) {
+            return Err(AuthError::InvalidScope((*scope).to_string()));
+        }
+
+        scopes.sort_unstable();
+        scopes.dedup();
+        Ok(scopes.join(" "))
+    }
+
+    pub fn contains(scope: &str, required: &str) -> bool {
+        scope.split_whitespace().any(|s| s == required)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected(result: Result<String, AuthError>) -> String {
+        match result {
+            Err(AuthError::InvalidScope(scope)) => scope,
+            other => panic!("expected invalid_scope, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn narrow_sorts_and_deduplicates() {
+        assert_eq!(
+            ScopeService::narrow("posts:write  posts:read posts:write", None)
+                .unwrap(),
+            "posts:read posts:write"
+        );
+    }
+
+    #[test]
+    fn narrow_rejects_empty_and_unknown_scopes() {
+        assert_eq!(rejected(ScopeService::narrow("  ", None)), "  ");
+        assert_eq!(
+            rejected(ScopeService::narrow("posts:read posts:delete", None)),
+            "posts:delete"
+        );
+    }
+
+    #[test]
+    fn narrow_stays_within_granted_scope() {
+        let granted = Some("posts:read posts:write");
+
+        assert_eq!(
+            ScopeService::narrow("posts:read", granted).unwrap(),
+            "posts:read"
+        );
+        assert_eq!(
+            rejected(ScopeService::narrow("posts:read users:write", granted)),
+            "users:write"
+        );
+    }
+
+    #[test]
+    fn contains_matches_whole_scopes_only() {
+        assert!(ScopeService::contains("posts:read admin", "admin"));
+        assert!(!ScopeService::contains("posts:read", "posts"));
+        assert!(!ScopeService::contains("", "posts:read"));
+    }
+}
@@ -0,0 +1,210 @@
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use rand::{RngCore, rngs::OsRng};
+use sha1::Sha1;
+use subtle::ConstantTimeEq;
+
+type HmacSha1 = Hmac<Sha1>;
+
+/// RFC 6238 defaults, the only parameters most authenticator apps support.
+const STEP_SECS: i64 = 30;
+const DIGITS: u32 = 6;
+const SECRET_LEN: usize = 20;
+/// Codes of the neighbouring time steps are accepted to allow for clock
+/// drift between the server and the phone.
+const ALLOWED_DRIFT_STEPS: i64 = 1;
+const RECOVERY_CODE_LEN: usize = 5;
+
+pub struct TotpService;
+
+impl TotpService {
+    /// Random 160-bit secret, base32 encoded as authenticator apps expect.
+    pub fn generate_secret() -> String {
+        let mut secret = [0u8; SECRET_LEN];
+        OsRng.fill_bytes(&mut secret);
+
+        BASE32_NOPAD.encode(&secret)
+    }
+
+    /// Key URI that authenticator apps import, usually from a QR code.
+    pub fn otpauth_uri(issuer: &str, account: &str, secret: &str) -> String {
+        let issuer = utf8_percent_encode(issuer, NON_ALPHANUMERIC);
+        let account = utf8_percent_encode(account, NON_ALPHANUMERIC);
+
+        format!(
+            "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}\
+             &algorithm=SHA1&digits={DIGITS}&period={STEP_SECS}"
+        )
+    }
+
+    /// RFC 4226 HOTP value of the secret for the given counter.
+    pub fn code_at(secret: &[u8], step: i64) -> u32 {
+        let mut mac = HmacSha1::new_from_slice(secret)
+            .expect("HMAC accepts keys of any length");
+        mac.update(&step.to_be_bytes());
+        let digest = mac.finalize().into_bytes();
+
+        let offset = usize::from(digest[digest.len() - 1] & 0x0f);
+        let binary = u32::from_be_bytes([
+            digest[offset] & 0x7f,
+            digest[offset + 1],
+            digest[offset + 2],
+            digest[offset + 3],
+        ]);
+
+        binary % 10u32.pow(DIGITS)
+    }
+
+    /// Checks a code against the secret at `unix_time` and returns the time
+    /// step it belongs to. Taking the time as an argument keeps this usable
+    /// with a fixed clock.
+    pub fn verify(secret: &str, code: &str, unix_time: i64) -> Option<i64> {
+        let secret = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+        let code = code.trim();
+        if code.len() != DIGITS as usize
+            || !code.bytes().all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+        let code: u32 = code.parse().ok()?;
+
+        // Every step is compared in constant time, also after a match, so
+        // the response time does not tell which step matched
+        let current = unix_time.div_euclid(STEP_SECS);
+        (current - ALLOWED_DRIFT_STEPS..=current + ALLOWED_DRIFT_STEPS).fold(
+            None,
+            |found, step| {
+                let matches = Self::code_at(&secret, step).ct_eq(&code);
+                found.or(bool::from(matches).then_some(step))
+            },
+        )
+    }
+
+    /// One-time recovery code such as `k3zq-7m2d`.
+    pub fn generate_recovery_code() -> String {
+        let mut bytes = [0u8; RECOVERY_CODE_LEN];
+        OsRng.fill_bytes(&mut bytes);
+
+        let code = BASE32_NOPAD.encode(&bytes).to_lowercase();
+        format!("{}-{}", &code[..4], &code[4..])
+    }
+
+    /// Recovery codes are compared without dashes, spaces or case.
+    pub fn normalize_recovery_code(code: &str) -> String {
+        code.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_lowercase())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The SHA-1 key from RFC 6238, appendix B.
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    fn rfc_secret_base32() -> String {
+        BASE32_NOPAD.encode(RFC_SECRET)
+    }
+
+    #[test]
+    fn code_at_matches_rfc_6238_vectors() {
+        // Последние шесть цифр восьмизначных значений из RFC
+        let vectors = [
+            (59, 287_082),
+            (1_111_111_109, 81_804),
+            (1_111_111_111, 50_471),
+            (1_234_567_890, 5_924),
+            (2_000_000_000, 279_037),
+            (20_000_000_000, 353_130),
+        ];
+
+        for (time, expected) in vectors {
+            assert_eq!(
+                TotpService::code_at(RFC_SECRET, time / STEP_SECS),
+                expected,
+                "T = {time}"
+            );
+        }
+    }
+
+    #[test]
+    fn verify_accepts_rfc_codes_and_returns_their_step() {
+        let secret = rfc_secret_base32();
+
+        assert_eq!(TotpService::verify(&secret, "287082", 59), Some(1));
+        assert_eq!(
+            TotpService::verify(&secret, "081804", 1_111_111_109),
+            Some(37_037_036)
+        );
+        assert_eq!(
+            TotpService::verify(&secret, "005924", 1_234_567_890),
+            Some(41_152_263)
+        );
+    }
+
+    #[test]
+    fn verify_allows_one_step_of_drift() {
+        let secret = rfc_secret_base32();
+
+        // Код шага 1 принимается на шагах 0..=2
+        assert_eq!(TotpService::verify(&secret, "287082", 0), Some(1));
+        assert_eq!(TotpService::verify(&secret, "287082", 89), Some(1));
+        assert_eq!(TotpService::verify(&secret, "287082", 90), None);
+    }
+
+    #[test]
+    fn verify_rejects_malformed_codes() {
+        let secret = rfc_secret_base32();
+
+        assert_eq!(TotpService::verify(&secret, "28708", 59), None);
+        assert_eq!(TotpService::verify(&secret, "0287082", 59), None);
+        assert_eq!(TotpService::verify(&secret, "28708a", 59), None);
+        assert_eq!(TotpService::verify(&secret, "", 59), None);
+        assert_eq!(TotpService::verify(&secret, " 287082 ", 59), Some(1));
+    }
+
+    #[test]
+    fn verify_rejects_invalid_secret() {
+        assert_eq!(TotpService::verify("not base32!", "287082", 59), None);
+    }
+
+    #[test]
+    fn generated_secret_is_160_bits() {
+        let secret = TotpService::generate_secret();
+        let decoded = BASE32_NOPAD.decode(secret.as_bytes()).unwrap();
+
+        assert_eq!(decoded.len(), SECRET_LEN);
+    }
+
+    #[test]
+    fn recovery_codes_are_normalized() {
+        assert_eq!(
+            TotpService::normalize_recovery_code("k3zq-7m2d"),
+            "k3zq7m2d"
+        );
+        assert_eq!(
+            TotpService::normalize_recovery_code("K3ZQ-7M2D"),
+            "k3zq7m2d"
+        );
+        assert_eq!(
+            TotpService::normalize_recovery_code(" k3zq 7m2d\n"),
+            "k3zq7m2d"
+        );
+    }
+
+    #[test]
+    fn generated_recovery_code_survives_normalization() {
+        let code = TotpService::generate_recovery_code();
+
+        assert_eq!(code.len(), 9);
+        assert_eq!(code.as_bytes()[4], b'-');
+        assert_eq!(
+            TotpService::normalize_recovery_code(&code),
+            code.replace('-', "")
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use std::{
+    collections::HashMap,
+    sync::{PoisonError, RwLock},
+};
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    errors::{auth_errors::AuthError, users_errors::UserError},
+    repositories::users_repository::UserRepository,
+};
+
+/// Internal ids of users by public id, for the `sub` of access tokens.
+/// Ids never change, so entries are kept for the life of the process and
+/// only the first token of a user hits the database.
+#[derive(Default)]
+pub struct UserIdCache {
+    ids: RwLock<HashMap<Uuid, i32>>,
+}
+
+impl UserIdCache {
+    /// Tokens of a deleted user are rejected as revoked.
+    pub async fn resolve(
+        &self,
+        pool: &PgPool,
+        public_id: Uuid,
+    ) -> Result<i32, AuthError> {
+        if let Some(&id) = self
+            .ids
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&public_id)
+        {
+            return Ok(id);
+        }
+
+        match UserRepository::find_id(pool, public_id).await {
+            Ok(Some(id)) => {
+                self.ids
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .insert(public_id, id);
+                Ok(id)
+            }
+            Ok(None) => {
+                log::warn!("Access token of deleted user {public_id}");
+                Err(AuthError::TokenRevoked)
+            }
+            Err(UserError::Database(e)) => Err(AuthError::Database(e)),
+            Err(e) => Err(AuthError::Authentication(e.to_string())),
+        }
+    }
+
+    /// Drops a deleted user, so their tokens stop resolving.
+    pub fn forget(&self, public_id: Uuid) {
+        self.ids
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&public_id);
+    }
+}